@@ -1,47 +1,93 @@
+use sudoku_variants_solver::error::ErrorCode;
+use sudoku_variants_solver::lookahead::propagation_lookahead_depth;
+#[cfg(feature = "parallel")]
+use sudoku_variants_solver::parallel::irrefutable_facts_parallel;
 use sudoku_variants_solver::puzzle::{
-    self, Arrow, Blocks, GivenNumbers, NonConsecutive, Pos, Puzzle, Skyscrapers, Thermo, XSums,
+    self, Arrow, Blocks, GivenNumbers, Killer, KillerRegion, NonConsecutive, Pos, Puzzle,
+    Skyscrapers, Thermo, XSums, XV,
 };
-use sudoku_variants_solver::solver::{irrefutable_facts, SolverConfig};
-
-const CONFIGS: [(&'static str, SolverConfig); 3] = [
-    (
-        "default",
-        SolverConfig {
-            optimize_polarity: false,
-            explicit_set_encoding: false,
-        },
-    ),
-    (
-        "opt",
-        SolverConfig {
-            optimize_polarity: true,
-            explicit_set_encoding: false,
-        },
-    ),
-    (
-        "expl_set",
-        SolverConfig {
-            optimize_polarity: true,
-            explicit_set_encoding: true,
-        },
-    ),
-];
+use sudoku_variants_solver::rating::rate;
+use sudoku_variants_solver::solver::{
+    irrefutable_facts, AdjacencyEncoding, OrderEncoding, SolveResult, SolverConfig, SumEncoding,
+};
+
+fn configs() -> Vec<(&'static str, SolverConfig)> {
+    let expl_set = SolverConfig {
+        optimize_polarity: true,
+        explicit_set_encoding: true,
+        sum_encoding: Some(SumEncoding::Combination),
+        order_encoding: Some(OrderEncoding::Order),
+        adjacency_encoding: Some(AdjacencyEncoding::Arithmetic),
+        ..SolverConfig::default()
+    };
+
+    vec![
+        (
+            "default",
+            SolverConfig {
+                optimize_polarity: false,
+                explicit_set_encoding: false,
+                ..expl_set.clone()
+            },
+        ),
+        (
+            "opt",
+            SolverConfig {
+                explicit_set_encoding: false,
+                ..expl_set.clone()
+            },
+        ),
+        ("expl_set", expl_set.clone()),
+        // Each of the following changes the encoding of a single constraint family.
+        (
+            "sum_lin",
+            SolverConfig {
+                sum_encoding: Some(SumEncoding::Linear),
+                ..expl_set.clone()
+            },
+        ),
+        (
+            "ord_dir",
+            SolverConfig {
+                order_encoding: Some(OrderEncoding::Direct),
+                ..expl_set.clone()
+            },
+        ),
+        (
+            "adj_tab",
+            SolverConfig {
+                adjacency_encoding: Some(AdjacencyEncoding::Table),
+                ..expl_set
+            },
+        ),
+        // The encodings chosen by `SolverConfig::resolve`, shown in the last column.
+        ("auto", SolverConfig::default()),
+    ]
+}
+
+/// The deepest lookahead tried for the lookahead column.
+const MAX_LOOKAHEAD_DEPTH: usize = 2;
 
 fn show_header() {
     print!("| {:25}", "Instance");
-    for (name, _) in CONFIGS.iter() {
+    for (name, _) in configs() {
         print!(" | {:8}", name);
     }
-    println!(" |");
+    #[cfg(feature = "parallel")]
+    print!(" | {:16}", format!("parallel ({})", num_threads()));
+    print!(" | auto choice (sum/order/adjacency)");
+    print!(" | {:28}", "rating");
+    println!(" | {:11} |", "lookahead");
 }
 
 fn run_bench(desc: &str, puzzle: Puzzle) {
     let mut expected_answer = None;
+    let mut times = vec![];
 
     print!("| {:25}", desc);
-    for (_, config) in CONFIGS.iter() {
+    for (_, config) in configs() {
         let start = std::time::Instant::now();
-        let answer = irrefutable_facts(&puzzle, *config);
+        let answer = irrefutable_facts(&puzzle, config).unwrap();
 
         if let Some(expected) = &expected_answer {
             assert_eq!(&answer, expected);
@@ -49,11 +95,69 @@ fn run_bench(desc: &str, puzzle: Puzzle) {
             expected_answer = Some(answer);
         }
 
-        let elapsed = start.elapsed();
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+        times.push(elapsed);
 
-        print!(" | {:8.2}", elapsed.as_secs_f64() * 1000.0);
+        print!(" | {:8.2}", elapsed);
     }
-    println!(" |");
+
+    #[cfg(feature = "parallel")]
+    {
+        let start = std::time::Instant::now();
+        let answer =
+            irrefutable_facts_parallel(&puzzle, SolverConfig::default(), num_threads()).unwrap();
+        assert_eq!(Some(answer), expected_answer);
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+
+        // The speedup is relative to the single-threaded run with the same config ("auto").
+        let auto_elapsed = times[times.len() - 1];
+        print!(" | {:8.2} ({:4.1}x)", elapsed, auto_elapsed / elapsed);
+    }
+
+    let auto = SolverConfig::default().resolve(&puzzle);
+    print!(
+        " | {:33}",
+        format!("{:?}/{:?}/{:?}", auto.sum, auto.order, auto.adjacency)
+    );
+
+    // Puzzles with rules the techniques ignore are not rated, so that they do not skew the
+    // calibration of the scores.
+    let rating = match rate(&puzzle, SolverConfig::default()) {
+        Ok(Some(rating)) => format!(
+            "{:4.1} {:?} ({} trials)",
+            rating.score, rating.tier, rating.trials
+        ),
+        Ok(None) => "no solution".to_string(),
+        Err(err) if err.code == ErrorCode::UnsupportedValue => {
+            format!("unsupported ({})", err.rule.unwrap_or_default())
+        }
+        Err(err) => panic!("{}", err),
+    };
+    print!(" | {:28}", rating);
+
+    // Without a unique solution, the lookahead would try every depth up to the maximum, which
+    // takes long on the puzzles with few clues.
+    let unique = matches!(
+        &expected_answer,
+        Some(SolveResult::Satisfiable(facts))
+            if facts.decided_nums.iter().flatten().all(|n| n.is_some())
+    );
+    let depth = if unique {
+        match propagation_lookahead_depth(&puzzle, MAX_LOOKAHEAD_DEPTH) {
+            Ok(Some(depth)) => depth.to_string(),
+            Ok(None) => format!("> {}", MAX_LOOKAHEAD_DEPTH),
+            Err(err) if err.code == ErrorCode::UnsupportedValue => "unsupported".to_string(),
+            Err(err) => panic!("{}", err),
+        }
+    } else {
+        "-".to_string()
+    };
+    println!(" | {:11} |", depth);
+}
+
+#[cfg(feature = "parallel")]
+fn num_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
 }
 
 fn default_blocks(block_size: usize) -> Blocks {
@@ -168,6 +272,27 @@ fn main() {
         },
     );
 
+    // A well-known classic puzzle which needs extensive trial and error.
+    run_bench(
+        "hard_classic",
+        Puzzle {
+            size: 9,
+            blocks: Some(default_blocks(3)),
+            given_numbers: given_numbers(&[
+                [8, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 3, 6, 0, 0, 0, 0, 0],
+                [0, 7, 0, 0, 9, 0, 2, 0, 0],
+                [0, 5, 0, 0, 0, 7, 0, 0, 0],
+                [0, 0, 0, 0, 4, 5, 7, 0, 0],
+                [0, 0, 0, 1, 0, 0, 0, 3, 0],
+                [0, 0, 1, 0, 0, 0, 0, 6, 8],
+                [0, 0, 8, 5, 0, 0, 0, 1, 0],
+                [0, 9, 0, 0, 0, 0, 4, 0, 0],
+            ]),
+            ..Default::default()
+        },
+    );
+
     run_bench(
         "few_clues_arrow",
         Puzzle {
@@ -276,6 +401,115 @@ fn main() {
         },
     );
 
+    run_bench(
+        "few_clues_killer",
+        Puzzle {
+            size: 9,
+            blocks: Some(default_blocks(3)),
+            given_numbers: given_numbers(&[
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 2, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 4, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+            ]),
+            killer: Some(Killer {
+                regions: vec![
+                    KillerRegion {
+                        cells: vec![Pos { x: 0, y: 0 }, Pos { x: 1, y: 0 }, Pos { x: 0, y: 1 }],
+                        sum: Some(7),
+                    },
+                    KillerRegion {
+                        cells: vec![Pos { x: 4, y: 4 }, Pos { x: 5, y: 4 }],
+                        sum: Some(17),
+                    },
+                    KillerRegion {
+                        cells: vec![
+                            Pos { x: 7, y: 7 },
+                            Pos { x: 8, y: 7 },
+                            Pos { x: 7, y: 8 },
+                            Pos { x: 8, y: 8 },
+                        ],
+                        sum: Some(12),
+                    },
+                ],
+                distinct: true,
+            }),
+            ..Default::default()
+        },
+    );
+
+    run_bench(
+        "killer_big_cages",
+        Puzzle {
+            size: 9,
+            blocks: Some(default_blocks(3)),
+            given_numbers: given_numbers(&[
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 2, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 4, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 0, 0, 0, 0, 0, 0, 0],
+            ]),
+            killer: Some(Killer {
+                regions: vec![
+                    KillerRegion {
+                        cells: (0..7).map(|x| Pos { x, y: 0 }).collect(),
+                        sum: Some(35),
+                    },
+                    KillerRegion {
+                        cells: (1..9).map(|y| Pos { x: 8, y }).collect(),
+                        sum: Some(40),
+                    },
+                ],
+                distinct: true,
+            }),
+            ..Default::default()
+        },
+    );
+
+    {
+        let mut horizontal = vec![vec![puzzle::XV_NO_CONSTRAINT; 9]; 8];
+        let mut vertical = vec![vec![puzzle::XV_NO_CONSTRAINT; 8]; 9];
+        horizontal[1][3] = puzzle::XV_X;
+        horizontal[5][6] = puzzle::XV_V;
+        vertical[2][2] = puzzle::XV_X;
+        vertical[7][4] = puzzle::XV_V;
+
+        run_bench(
+            "few_clues_xv",
+            Puzzle {
+                size: 9,
+                blocks: Some(default_blocks(3)),
+                given_numbers: given_numbers(&[
+                    [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    [0, 0, 0, 0, 0, 0, 5, 0, 0],
+                    [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    [0, 0, 3, 0, 0, 0, 0, 0, 0],
+                    [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                    [0, 0, 0, 0, 0, 0, 0, 6, 0],
+                    [0, 0, 0, 0, 0, 0, 0, 0, 0],
+                ]),
+                xv: Some(XV {
+                    horizontal,
+                    vertical,
+                    all_shown: true,
+                }),
+                ..Default::default()
+            },
+        );
+    }
+
     if full {
         run_bench(
             "no_clue_16x16",
@@ -289,6 +523,77 @@ fn main() {
             },
         );
 
+        run_bench(
+            "killer_16x16",
+            Puzzle {
+                size: 16,
+                blocks: Some(default_blocks(4)),
+                given_numbers: puzzle::GivenNumbers {
+                    numbers: vec![vec![None; 16]; 16],
+                },
+                killer: Some(Killer {
+                    regions: vec![
+                        KillerRegion {
+                            cells: vec![Pos { x: 0, y: 0 }, Pos { x: 1, y: 0 }, Pos { x: 0, y: 1 }],
+                            sum: Some(6),
+                        },
+                        KillerRegion {
+                            cells: vec![Pos { x: 7, y: 7 }, Pos { x: 8, y: 7 }],
+                            sum: Some(31),
+                        },
+                        KillerRegion {
+                            cells: vec![
+                                Pos { x: 12, y: 12 },
+                                Pos { x: 13, y: 12 },
+                                Pos { x: 12, y: 13 },
+                                Pos { x: 13, y: 13 },
+                            ],
+                            sum: Some(30),
+                        },
+                    ],
+                    distinct: true,
+                }),
+                ..Default::default()
+            },
+        );
+
+        run_bench(
+            "thermo_16x16",
+            Puzzle {
+                size: 16,
+                blocks: Some(default_blocks(4)),
+                given_numbers: puzzle::GivenNumbers {
+                    numbers: vec![vec![None; 16]; 16],
+                },
+                thermo: Some(Thermo {
+                    thermos: vec![
+                        (0..8).map(|x| Pos { x, y: 2 }).collect(),
+                        (4..12).map(|i| Pos { x: i, y: i }).collect(),
+                    ],
+                }),
+                ..Default::default()
+            },
+        );
+
+        {
+            let mut given_numbers = vec![vec![None; 16]; 16];
+            given_numbers[2][3] = Some(1);
+            given_numbers[9][12] = Some(16);
+
+            run_bench(
+                "noncon_16x16",
+                Puzzle {
+                    size: 16,
+                    blocks: Some(default_blocks(4)),
+                    given_numbers: puzzle::GivenNumbers {
+                        numbers: given_numbers,
+                    },
+                    non_consecutive: Some(NonConsecutive {}),
+                    ..Default::default()
+                },
+            );
+        }
+
         {
             let mut given_numbers = vec![vec![None; 16]; 16];
             given_numbers[5][6] = Some(4);
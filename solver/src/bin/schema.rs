@@ -0,0 +1,8 @@
+//! Prints the JSON Schema of the puzzle format accepted by the solver.
+
+use sudoku_variants_solver::puzzle::Puzzle;
+
+fn main() {
+    let schema = schemars::schema_for!(Puzzle);
+    println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+}
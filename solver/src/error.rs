@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    /// The puzzle JSON could not be parsed.
+    ParseError,
+    /// A grid-shaped field does not match the board size.
+    ShapeMismatch,
+    /// A cell position lies outside the board.
+    OutOfRange,
+    /// A value is not supported by the rule (e.g. an unknown XV kind).
+    UnsupportedValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SolverError {
+    pub code: ErrorCode,
+
+    /// The JSON key of the offending rule, if the error is specific to a rule.
+    pub rule: Option<String>,
+
+    pub message: String,
+}
+
+impl SolverError {
+    pub fn new(code: ErrorCode, rule: Option<&str>, message: impl Into<String>) -> SolverError {
+        SolverError {
+            code,
+            rule: rule.map(|rule| rule.to_string()),
+            message: message.into(),
+        }
+    }
+
+    pub fn parse_error(message: impl Into<String>) -> SolverError {
+        SolverError::new(ErrorCode::ParseError, None, message)
+    }
+
+    pub fn shape_mismatch(rule: &str, message: impl Into<String>) -> SolverError {
+        SolverError::new(ErrorCode::ShapeMismatch, Some(rule), message)
+    }
+
+    pub fn out_of_range(rule: &str, message: impl Into<String>) -> SolverError {
+        SolverError::new(ErrorCode::OutOfRange, Some(rule), message)
+    }
+
+    pub fn unsupported_value(rule: &str, message: impl Into<String>) -> SolverError {
+        SolverError::new(ErrorCode::UnsupportedValue, Some(rule), message)
+    }
+}
+
+impl std::fmt::Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.rule {
+            Some(rule) => write!(f, "{:?} in {}: {}", self.code, rule, self.message),
+            None => write!(f, "{:?}: {}", self.code, self.message),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
@@ -0,0 +1,34 @@
+use crate::error::SolverError;
+use crate::puzzle::{Pos, Puzzle};
+use crate::solver::{explain, Assumption, Budget, RuleInstance, SolverConfig};
+
+/// Explains why `digit` cannot be placed at `cell`: returns a minimal set of rule instances
+/// which already forbid it, or `None` if some solution places it there.
+pub fn explain_elimination(
+    puzzle: &Puzzle,
+    config: SolverConfig,
+    cell: Pos,
+    digit: i32,
+) -> Result<Option<Vec<RuleInstance>>, SolverError> {
+    if cell.y >= puzzle.height() || cell.x >= puzzle.width() {
+        return Err(SolverError::out_of_range(
+            "cell",
+            format!("cell (y: {}, x: {}) is outside the board", cell.y, cell.x),
+        ));
+    }
+    let digits = puzzle.digits();
+    if digits.binary_search(&digit).is_err() {
+        return Err(SolverError::out_of_range(
+            "digit",
+            format!("digit {} is not in the digit set {:?}", digit, digits),
+        ));
+    }
+    let budget = Budget::new(&config);
+    explain(
+        puzzle,
+        &config,
+        &budget,
+        None,
+        Some(Assumption::Place(cell, digit)),
+    )
+}
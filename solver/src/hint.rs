@@ -0,0 +1,193 @@
+use serde::Serialize;
+
+use crate::error::SolverError;
+use crate::progress::{is_possible, PlayerState};
+use crate::puzzle::{Pos, Puzzle};
+use crate::solver::{
+    explain, irrefutable_facts, Assumption, Budget, IrrefutableFacts, RuleInstance, SolveResult,
+    SolverConfig,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HintKind {
+    /// `digit` is the only possible digit at `cell`.
+    Placement,
+
+    /// `digit` is impossible at `cell`.
+    Elimination,
+}
+
+/// The next deduction the player can make.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hint {
+    pub kind: HintKind,
+    pub cell: Pos,
+    pub digit: i32,
+
+    /// A set of rule instances from which the deduction follows. It is subset-minimal (no
+    /// instance can be dropped), but a smaller set may exist. Entered digits appear as
+    /// `givenNumbers` instances.
+    pub reason: Vec<RuleInstance>,
+}
+
+/// Finds the deduction which the player has not made yet and which has the smallest
+/// justification: placements of digits in empty cells and eliminations of digits still in the
+/// pencil marks (or of any digit in cells without pencil marks).
+///
+/// Justifications are compared by the size of the subset-minimal sets QuickXplain returns, so
+/// the hint is not guaranteed to have the smallest justification overall. The search stops at
+/// the first justification by a single instance, which nothing can beat.
+///
+/// Among deductions with equally small justifications, placements are preferred. Returns
+/// `None` if there is nothing left to deduce or the entries leave no solution (see
+/// `check_progress`). If `config` has a time limit, the best deduction found within it is
+/// returned.
+pub fn next_hint(
+    puzzle: &Puzzle,
+    state: &PlayerState,
+    config: SolverConfig,
+) -> Result<Option<Hint>, SolverError> {
+    state.check_shape(puzzle)?;
+    let current = state.apply(puzzle);
+    let budget = Budget::new(&config);
+    let facts = match irrefutable_facts(&current, config.clone())? {
+        SolveResult::Satisfiable(facts) => facts,
+        SolveResult::Unsatisfiable { .. } => return Ok(None),
+    };
+
+    let deductions = deductions(&current, &facts, |y, x, digit| {
+        state
+            .pencil_marks(y, x)
+            .is_none_or(|marks| marks.contains(&digit))
+    });
+
+    let mut best: Option<Hint> = None;
+    for (kind, cell, digit) in deductions {
+        // Placements come first, so a later deduction of the same size would not be taken.
+        if best.as_ref().is_some_and(|best| best.reason.len() <= 1) {
+            break;
+        }
+        if budget.is_exhausted() && best.is_some() {
+            break;
+        }
+        let Some(reason) = justify(&current, &config, &budget, kind, cell, digit)? else {
+            continue;
+        };
+        if best
+            .as_ref()
+            .is_none_or(|best| reason.len() < best.reason.len())
+        {
+            best = Some(Hint {
+                kind,
+                cell,
+                digit,
+                reason,
+            });
+        }
+    }
+
+    Ok(best)
+}
+
+/// The deductions which can be made from the givens of a puzzle, for setters checking the
+/// intended break-in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BreakIns {
+    /// The deductions, ranked by the size of their justification (see `Hint::reason`). Among
+    /// deductions with equally small justifications, placements come first.
+    pub deductions: Vec<Hint>,
+
+    /// Whether the time limit of the config was reached before all deductions were explained.
+    /// If so, only the explained ones are listed.
+    #[serde(rename = "timedOut")]
+    pub timed_out: bool,
+}
+
+/// Finds all placements and eliminations which follow from the givens of `puzzle` and ranks
+/// them by the size of their justification, so that the easiest break-in comes first.
+///
+/// The justifications are the subset-minimal sets QuickXplain returns, which only approximate
+/// the smallest ones: a deduction may rank lower than a smaller justification would put it.
+///
+/// Returns `None` if the puzzle has no solution. If `config` has a time limit, only the
+/// deductions explained within it are returned.
+pub fn find_break_ins(
+    puzzle: &Puzzle,
+    config: SolverConfig,
+) -> Result<Option<BreakIns>, SolverError> {
+    let budget = Budget::new(&config);
+    let facts = match irrefutable_facts(puzzle, config.clone())? {
+        SolveResult::Satisfiable(facts) => facts,
+        SolveResult::Unsatisfiable { .. } => return Ok(None),
+    };
+
+    let mut found = vec![];
+    let mut timed_out = facts.timed_out;
+    for (kind, cell, digit) in deductions(puzzle, &facts, |_, _, _| true) {
+        if budget.is_exhausted() {
+            timed_out = true;
+            break;
+        }
+        if let Some(reason) = justify(puzzle, &config, &budget, kind, cell, digit)? {
+            found.push(Hint {
+                kind,
+                cell,
+                digit,
+                reason,
+            });
+        }
+    }
+
+    // The sort is stable, so placements stay ahead of eliminations of the same size.
+    found.sort_by_key(|hint| hint.reason.len());
+    Ok(Some(BreakIns {
+        deductions: found,
+        timed_out,
+    }))
+}
+
+/// Returns the deductions `facts` allow in the empty cells of `puzzle`, placements first.
+/// Eliminations are only included if `marked` holds for the candidate.
+fn deductions(
+    puzzle: &Puzzle,
+    facts: &IrrefutableFacts,
+    marked: impl Fn(usize, usize, i32) -> bool,
+) -> Vec<(HintKind, Pos, i32)> {
+    let mut deductions = vec![];
+    for y in 0..puzzle.height() {
+        for x in 0..puzzle.width() {
+            if puzzle.given_numbers.numbers[y][x].is_some() {
+                continue;
+            }
+            let cell = Pos { x, y };
+            if let Some(digit) = facts.decided_nums[y][x] {
+                deductions.push((HintKind::Placement, cell, digit));
+            }
+            for &digit in &facts.digits {
+                if marked(y, x, digit) && !is_possible(facts, y, x, digit) {
+                    deductions.push((HintKind::Elimination, cell, digit));
+                }
+            }
+        }
+    }
+
+    deductions.sort_by_key(|&(kind, _, _)| kind != HintKind::Placement);
+    deductions
+}
+
+/// Returns a subset-minimal set of rule instances from which the deduction follows.
+fn justify(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    budget: &Budget,
+    kind: HintKind,
+    cell: Pos,
+    digit: i32,
+) -> Result<Option<Vec<RuleInstance>>, SolverError> {
+    let assumption = match kind {
+        HintKind::Placement => Assumption::Exclude(cell, digit),
+        HintKind::Elimination => Assumption::Place(cell, digit),
+    };
+    explain(puzzle, config, budget, None, Some(assumption))
+}
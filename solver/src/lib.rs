@@ -1,25 +1,193 @@
+pub mod error;
+pub mod explain;
+pub mod hint;
+pub mod logic;
+pub mod lookahead;
+pub mod migrate;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod progress;
 pub mod puzzle;
+pub mod rating;
+pub mod rule;
 pub mod solver;
+pub mod trace;
+pub mod validate;
+
+use serde::Serialize;
+
+use error::SolverError;
 
 static mut SHARED_ARRAY: Vec<u8> = Vec::new();
 
-#[no_mangle]
-fn solve_problem(puzzle_json: *const u8, len: usize) -> *const u8 {
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+enum Response<T> {
+    Ok { result: T },
+    Error(SolverError),
+}
+
+impl<T> From<Result<T, SolverError>> for Response<T> {
+    fn from(res: Result<T, SolverError>) -> Self {
+        match res {
+            Ok(result) => Response::Ok { result },
+            Err(err) => Response::Error(err),
+        }
+    }
+}
+
+fn parse_puzzle(puzzle_json: *const u8, len: usize) -> Result<puzzle::Puzzle, SolverError> {
     let puzzle_json = unsafe { std::slice::from_raw_parts(puzzle_json, len) };
-    let puzzle: puzzle::Puzzle = serde_json::from_slice(puzzle_json).unwrap(); // TODO: handle error
+    rule::RuleRegistry::default().parse_puzzle(puzzle_json)
+}
 
-    let answer = solver::irrefutable_facts(&puzzle, solver::SolverConfig::default());
-    let answer_str = serde_json::to_string(&answer).unwrap(); // TODO: handle error
+/// Writes `response` to the shared buffer as a 4-byte little-endian length followed by the JSON body.
+fn write_response<T: Serialize>(response: Response<T>) -> *const u8 {
+    // Responses consist only of plain structs and enums, so serialization cannot fail.
+    let response_str = serde_json::to_string(&response).unwrap();
 
-    unsafe {
-        let answer_len = answer_str.len();
+    let shared_array = unsafe { &mut *std::ptr::addr_of_mut!(SHARED_ARRAY) };
+    let response_len = response_str.len();
+
+    shared_array.clear();
+    shared_array.push((response_len & 0xFF) as u8);
+    shared_array.push(((response_len >> 8) & 0xFF) as u8);
+    shared_array.push(((response_len >> 16) & 0xFF) as u8);
+    shared_array.push(((response_len >> 24) & 0xFF) as u8);
+    shared_array.extend(response_str.as_bytes());
+    shared_array.as_ptr()
+}
+
+/// The default config with a time limit of `time_limit_ms` milliseconds, or none if it is 0.
+fn config_with_time_limit(time_limit_ms: u32) -> solver::SolverConfig {
+    solver::SolverConfig {
+        time_limit: (time_limit_ms > 0)
+            .then(|| std::time::Duration::from_millis(time_limit_ms as u64)),
+        ..solver::SolverConfig::default()
+    }
+}
 
-        SHARED_ARRAY.clear();
-        SHARED_ARRAY.push((answer_len & 0xFF) as u8);
-        SHARED_ARRAY.push(((answer_len >> 8) & 0xFF) as u8);
-        SHARED_ARRAY.push(((answer_len >> 16) & 0xFF) as u8);
-        SHARED_ARRAY.push(((answer_len >> 24) & 0xFF) as u8);
-        SHARED_ARRAY.extend(answer_str.as_bytes());
-        SHARED_ARRAY.as_ptr()
+/// Parses the puzzle and rejects it if it has error-severity diagnostics.
+fn parse_valid_puzzle(puzzle_json: *const u8, len: usize) -> Result<puzzle::Puzzle, SolverError> {
+    let puzzle = parse_puzzle(puzzle_json, len)?;
+    if let Some(err) = validate::first_error(&puzzle) {
+        return Err(err);
     }
+    Ok(puzzle)
+}
+
+/// The version of the puzzle format the solver expects (see `migrate::CURRENT_VERSION`).
+#[no_mangle]
+fn puzzle_version() -> u32 {
+    migrate::CURRENT_VERSION
+}
+
+#[no_mangle]
+fn solve_problem(puzzle_json: *const u8, len: usize) -> *const u8 {
+    let answer = parse_valid_puzzle(puzzle_json, len)
+        .and_then(|puzzle| solver::irrefutable_facts(&puzzle, solver::SolverConfig::default()));
+
+    write_response(Response::from(answer))
+}
+
+/// Like `solve_problem`, but returns the facts known so far after about `time_limit_ms`
+/// milliseconds, or has no limit if it is 0 (see `SolverConfig::time_limit`).
+#[no_mangle]
+fn solve_problem_with_time_limit(
+    puzzle_json: *const u8,
+    len: usize,
+    time_limit_ms: u32,
+) -> *const u8 {
+    let answer = parse_valid_puzzle(puzzle_json, len).and_then(|puzzle| {
+        solver::irrefutable_facts(&puzzle, config_with_time_limit(time_limit_ms))
+    });
+
+    write_response(Response::from(answer))
+}
+
+#[no_mangle]
+fn count_solutions(puzzle_json: *const u8, len: usize, limit: usize) -> *const u8 {
+    let count = parse_valid_puzzle(puzzle_json, len).and_then(|puzzle| {
+        solver::count_solutions(&puzzle, limit, solver::SolverConfig::default())
+    });
+
+    write_response(Response::from(count))
+}
+
+/// Checks the player's entries, given as the `answer` key of the puzzle JSON (see
+/// `progress::PlayerState`).
+#[no_mangle]
+fn check_progress(puzzle_json: *const u8, len: usize) -> *const u8 {
+    let report = parse_valid_puzzle(puzzle_json, len).and_then(|puzzle| {
+        let state = progress::PlayerState::from_puzzle(&puzzle)?;
+        progress::check_progress(&puzzle, &state, solver::SolverConfig::default())
+    });
+
+    write_response(Response::from(report))
+}
+
+/// Returns the next deduction for the player's entries (see `hint::next_hint`), searching for
+/// about `time_limit_ms` milliseconds (0 for no limit).
+#[no_mangle]
+fn next_hint(puzzle_json: *const u8, len: usize, time_limit_ms: u32) -> *const u8 {
+    let hint = parse_valid_puzzle(puzzle_json, len).and_then(|puzzle| {
+        let state = progress::PlayerState::from_puzzle(&puzzle)?;
+        hint::next_hint(&puzzle, &state, config_with_time_limit(time_limit_ms))
+    });
+
+    write_response(Response::from(hint))
+}
+
+/// Ranks the deductions from the givens by the size of their justification (see
+/// `hint::find_break_ins`), searching for about `time_limit_ms` milliseconds (0 for no limit).
+#[no_mangle]
+fn find_break_ins(puzzle_json: *const u8, len: usize, time_limit_ms: u32) -> *const u8 {
+    let break_ins = parse_valid_puzzle(puzzle_json, len)
+        .and_then(|puzzle| hint::find_break_ins(&puzzle, config_with_time_limit(time_limit_ms)));
+
+    write_response(Response::from(break_ins))
+}
+
+/// Solves the puzzle with human techniques and returns the steps (see `trace::Trace`).
+#[no_mangle]
+fn trace_logical_solve(puzzle_json: *const u8, len: usize) -> *const u8 {
+    let trace =
+        parse_valid_puzzle(puzzle_json, len).and_then(|puzzle| trace::trace_logical_solve(&puzzle));
+
+    write_response(Response::from(trace))
+}
+
+#[no_mangle]
+fn validate_problem(puzzle_json: *const u8, len: usize) -> *const u8 {
+    let diagnostics = parse_puzzle(puzzle_json, len).map(|puzzle| puzzle.validate());
+
+    write_response(Response::from(diagnostics))
+}
+
+#[no_mangle]
+fn find_ambiguity(puzzle_json: *const u8, len: usize) -> *const u8 {
+    let ambiguity = parse_valid_puzzle(puzzle_json, len)
+        .and_then(|puzzle| solver::find_ambiguity(&puzzle, solver::SolverConfig::default()));
+
+    write_response(Response::from(ambiguity))
+}
+
+#[no_mangle]
+fn explain_elimination(
+    puzzle_json: *const u8,
+    len: usize,
+    y: usize,
+    x: usize,
+    digit: i32,
+) -> *const u8 {
+    let reason = parse_valid_puzzle(puzzle_json, len).and_then(|puzzle| {
+        explain::explain_elimination(
+            &puzzle,
+            solver::SolverConfig::default(),
+            puzzle::Pos { x, y },
+            digit,
+        )
+    });
+
+    write_response(Response::from(reason))
 }
@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+
+use super::{bits, Grid, Link, Step, Technique};
+
+/// The maximum number of candidates in a chain.
+const MAX_CHAIN_LENGTH: usize = 16;
+
+/// An alternating inference chain whose strong links are all within bivalue cells and whose
+/// weak links all connect the same digit in different cells.
+pub(super) fn xy_chain(grid: &Grid) -> Option<Step> {
+    chain(grid, true, Technique::XYChain)
+}
+
+/// A chain of candidates connected alternately by strong and weak links, starting and ending
+/// with a strong link: one of its ends is true, so candidates seeing both ends are false.
+pub(super) fn alternating_inference_chain(grid: &Grid) -> Option<Step> {
+    chain(grid, false, Technique::AlternatingInferenceChain)
+}
+
+/// A candidate, as (y, x, digit index).
+type Node = (usize, usize, usize);
+
+/// The strong and weak links of the candidates of a grid.
+struct Links {
+    nodes: Vec<Node>,
+    strong: Vec<Vec<usize>>,
+    weak: Vec<Vec<usize>>,
+
+    /// The houses through which each strong link goes, if any, keyed by the node pair.
+    strong_houses: Vec<Vec<(usize, usize)>>,
+
+    /// The candidates sharing a cell or a house (for the same digit) with each candidate, in
+    /// ascending order.
+    peers: Vec<Vec<usize>>,
+}
+
+impl Links {
+    #[allow(clippy::needless_range_loop)]
+    fn new(grid: &Grid, xy_only: bool) -> Links {
+        let (h, w) = (grid.height(), grid.width());
+        let mut nodes = vec![];
+        let mut id = vec![vec![vec![None; grid.digits.len()]; w]; h];
+        for y in 0..h {
+            for x in 0..w {
+                for i in bits(grid.candidates[y][x]) {
+                    id[y][x][i] = Some(nodes.len());
+                    nodes.push((y, x, i));
+                }
+            }
+        }
+
+        let mut strong = vec![vec![]; nodes.len()];
+        let mut weak = vec![vec![]; nodes.len()];
+        let mut strong_houses = vec![vec![]; nodes.len()];
+        let mut peers = vec![vec![]; nodes.len()];
+        for (n, &(y, x, i)) in nodes.iter().enumerate() {
+            let candidates = grid.candidates[y][x];
+            for j in bits(candidates) {
+                if j == i {
+                    continue;
+                }
+                let m = id[y][x][j].unwrap();
+                peers[n].push(m);
+                if candidates.count_ones() == 2 {
+                    strong[n].push(m);
+                }
+                if !xy_only {
+                    weak[n].push(m);
+                }
+            }
+
+            for &hi in grid.houses_of(y, x) {
+                let house = &grid.houses[hi];
+                let positions = grid.positions(house, i);
+                for &(y2, x2) in &positions {
+                    if (y2, x2) == (y, x) {
+                        continue;
+                    }
+                    let m = id[y2][x2][i].unwrap();
+                    peers[n].push(m);
+                    if !weak[n].contains(&m) {
+                        weak[n].push(m);
+                    }
+                    let complete = house.digits.is_some_and(|digits| (digits >> i) & 1 != 0);
+                    if !xy_only && complete && positions.len() == 2 {
+                        if !strong[n].contains(&m) {
+                            strong[n].push(m);
+                        }
+                        strong_houses[n].push((m, hi));
+                    }
+                }
+            }
+        }
+
+        for peers in &mut peers {
+            peers.sort();
+            peers.dedup();
+        }
+
+        Links {
+            nodes,
+            strong,
+            weak,
+            strong_houses,
+            peers,
+        }
+    }
+
+    /// Returns the candidates which share a cell or a house (for the same digit) with both `a`
+    /// and `b`.
+    fn seen_by_both(&self, a: usize, b: usize) -> Vec<usize> {
+        self.peers[a]
+            .iter()
+            .copied()
+            .filter(|&n| n != b && self.peers[b].binary_search(&n).is_ok())
+            .collect()
+    }
+}
+
+fn chain(grid: &Grid, xy_only: bool, technique: Technique) -> Option<Step> {
+    let links = Links::new(grid, xy_only);
+    let num_nodes = links.nodes.len();
+
+    // The shortest chain with eliminations, as its nodes and the eliminated candidates.
+    let mut best: Option<(Vec<usize>, Vec<usize>)> = None;
+    for start in 0..num_nodes {
+        let max_length = best
+            .as_ref()
+            .map_or(MAX_CHAIN_LENGTH, |(chain, _)| chain.len() - 1);
+
+        // States are (node, whether the chain arrived at it by a strong link). The next link
+        // must be of the other kind.
+        let state = |node: usize, strong: bool| node * 2 + strong as usize;
+        let mut parent = vec![None; num_nodes * 2];
+        let mut length = vec![0; num_nodes * 2];
+        let mut queue = VecDeque::new();
+        parent[state(start, false)] = Some(usize::MAX);
+        length[state(start, false)] = 1;
+        queue.push_back((start, false));
+
+        while let Some((node, strong)) = queue.pop_front() {
+            let current = state(node, strong);
+            if strong && node != start {
+                let eliminated = links.seen_by_both(start, node);
+                if !eliminated.is_empty() {
+                    let mut chain = vec![];
+                    let mut s = current;
+                    while s != usize::MAX {
+                        chain.push(s / 2);
+                        s = parent[s].unwrap();
+                    }
+                    chain.reverse();
+                    best = Some((chain, eliminated));
+                    break;
+                }
+            }
+            if length[current] >= max_length {
+                continue;
+            }
+
+            let next = if strong {
+                &links.weak[node]
+            } else {
+                &links.strong[node]
+            };
+            for &m in next {
+                let s = state(m, !strong);
+                if m == start || parent[s].is_some() {
+                    continue;
+                }
+                parent[s] = Some(current);
+                length[s] = length[current] + 1;
+                queue.push_back((m, !strong));
+            }
+        }
+    }
+
+    let (chain, eliminated) = best?;
+    let candidate = |n: usize| {
+        let (y, x, i) = links.nodes[n];
+        grid.candidate(y, x, i)
+    };
+
+    let mut houses = vec![];
+    let mut chain_links = vec![];
+    for (k, pair) in chain.windows(2).enumerate() {
+        let strong = k % 2 == 0;
+        if strong {
+            for &(m, h) in &links.strong_houses[pair[0]] {
+                let instance = &grid.houses[h].instance;
+                if m == pair[1] && !houses.contains(instance) {
+                    houses.push(instance.clone());
+                }
+            }
+        }
+        chain_links.push(Link {
+            from: candidate(pair[0]),
+            to: candidate(pair[1]),
+            strong,
+        });
+    }
+
+    Some(Step {
+        technique,
+        houses,
+        candidates: chain.iter().map(|&n| candidate(n)).collect(),
+        links: chain_links,
+        placements: vec![],
+        eliminations: eliminated.into_iter().map(candidate).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::puzzle::{Candidate, Pos, Puzzle};
+
+    fn candidate(y: usize, x: usize, digit: i32) -> Candidate {
+        Candidate {
+            cell: Pos { x, y },
+            digit,
+        }
+    }
+
+    #[test]
+    fn xy_chain_eliminates_the_end_digit() {
+        let puzzle: Puzzle = serde_json::from_value(json!({
+            "size": 4,
+            "givenNumbers": { "numbers": vec![vec![None::<i32>; 4]; 4] }
+        }))
+        .unwrap();
+        let mut grid = Grid::new(&puzzle).unwrap();
+        // (0, 0) = {1, 2}, (0, 2) = {2, 3} and (2, 2) = {1, 3}: one of the ends is 1.
+        grid.candidates[0][0] = 0b0011;
+        grid.candidates[0][2] = 0b0110;
+        grid.candidates[2][2] = 0b0101;
+
+        let step = xy_chain(&grid).unwrap();
+        assert_eq!(
+            step.candidates,
+            vec![
+                candidate(0, 0, 1),
+                candidate(0, 0, 2),
+                candidate(0, 2, 2),
+                candidate(0, 2, 3),
+                candidate(2, 2, 3),
+                candidate(2, 2, 1),
+            ]
+        );
+        assert_eq!(step.eliminations, vec![candidate(2, 0, 1)]);
+    }
+}
@@ -0,0 +1,190 @@
+use super::{Grid, House, Step, Technique};
+
+pub(super) fn x_wing(grid: &Grid) -> Option<Step> {
+    fish(grid, 2, Technique::XWing)
+}
+
+pub(super) fn swordfish(grid: &Grid) -> Option<Step> {
+    fish(grid, 3, Technique::Swordfish)
+}
+
+pub(super) fn jellyfish(grid: &Grid) -> Option<Step> {
+    fish(grid, 4, Technique::Jellyfish)
+}
+
+/// `size` houses (the base) which must each contain a digit and whose positions for it lie in
+/// `size` other houses (the cover): the digit can be removed from the rest of the cover.
+///
+/// Any houses can be used, e.g. rows as the base and columns as the cover for the classic
+/// fish, but also blocks, diagonals or extra regions.
+fn fish(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    for i in 0..grid.digits.len() {
+        let bases = grid
+            .houses
+            .iter()
+            .filter(|house| house.digits.is_some_and(|digits| (digits >> i) & 1 != 0))
+            .filter(|house| (2..=size).contains(&grid.positions(house, i).len()))
+            .collect::<Vec<_>>();
+
+        let mut chosen = vec![];
+        if let Some(step) = find_base(grid, i, size, technique, &bases, 0, &mut chosen) {
+            return Some(step);
+        }
+    }
+    None
+}
+
+/// Extends `chosen` by bases from `bases[start..]` whose positions do not overlap, and looks
+/// for a cover once there are `size` of them.
+fn find_base<'a>(
+    grid: &'a Grid,
+    i: usize,
+    size: usize,
+    technique: Technique,
+    bases: &[&'a House],
+    start: usize,
+    chosen: &mut Vec<&'a House>,
+) -> Option<Step> {
+    if chosen.len() == size {
+        let positions = chosen
+            .iter()
+            .flat_map(|house| grid.positions(house, i))
+            .collect::<Vec<_>>();
+        let mut covers = vec![];
+        return find_cover(grid, i, technique, chosen, &positions, &mut covers);
+    }
+
+    for j in start..bases.len() {
+        let base = bases[j];
+        let overlaps = grid.positions(base, i).iter().any(|cell| {
+            chosen
+                .iter()
+                .any(|house| grid.positions(house, i).contains(cell))
+        });
+        if overlaps {
+            continue;
+        }
+        chosen.push(base);
+        let step = find_base(grid, i, size, technique, bases, j + 1, chosen);
+        chosen.pop();
+        if step.is_some() {
+            return step;
+        }
+    }
+    None
+}
+
+/// Extends `covers` by houses containing the first position not covered yet, until all
+/// `positions` are covered by `bases.len()` houses.
+fn find_cover<'a>(
+    grid: &'a Grid,
+    i: usize,
+    technique: Technique,
+    bases: &[&'a House],
+    positions: &[(usize, usize)],
+    covers: &mut Vec<&'a House>,
+) -> Option<Step> {
+    let uncovered = positions
+        .iter()
+        .find(|cell| !covers.iter().any(|house| house.cells.contains(cell)));
+    let Some(&(y, x)) = uncovered else {
+        let mut eliminated = covers
+            .iter()
+            .flat_map(|house| grid.positions(house, i))
+            .filter(|cell| !positions.contains(cell))
+            .collect::<Vec<_>>();
+        if eliminated.is_empty() {
+            return None;
+        }
+        eliminated.sort();
+        eliminated.dedup();
+
+        return Some(Step {
+            technique,
+            houses: bases
+                .iter()
+                .chain(covers.iter())
+                .map(|house| house.instance.clone())
+                .collect(),
+            candidates: positions
+                .iter()
+                .map(|&(y, x)| grid.candidate(y, x, i))
+                .collect(),
+            links: vec![],
+            placements: vec![],
+            eliminations: eliminated
+                .into_iter()
+                .map(|(y, x)| grid.candidate(y, x, i))
+                .collect(),
+        });
+    };
+    if covers.len() == bases.len() {
+        return None;
+    }
+
+    for &h in grid.houses_of(y, x) {
+        let cover = &grid.houses[h];
+        if bases.iter().any(|base| std::ptr::eq(*base, cover)) {
+            continue;
+        }
+        covers.push(cover);
+        let step = find_cover(grid, i, technique, bases, positions, covers);
+        covers.pop();
+        if step.is_some() {
+            return step;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::puzzle::{Candidate, Pos, Puzzle};
+
+    #[test]
+    fn x_wing_on_extra_regions() {
+        let puzzle: Puzzle = serde_json::from_value(json!({
+            "size": 4,
+            "givenNumbers": { "numbers": vec![vec![None::<i32>; 4]; 4] },
+            "extraRegions": {
+                "regions": [
+                    { "cells": [{ "x": 0, "y": 0 }, { "x": 1, "y": 1 }, { "x": 2, "y": 2 }, { "x": 3, "y": 3 }] },
+                    { "cells": [{ "x": 3, "y": 0 }, { "x": 2, "y": 1 }, { "x": 1, "y": 2 }, { "x": 0, "y": 3 }] }
+                ]
+            }
+        }))
+        .unwrap();
+        let mut grid = Grid::new(&puzzle).unwrap();
+        // Both regions can only have their 1 in row 0 or row 1.
+        for (y, x) in [(2, 2), (3, 3), (2, 1), (3, 0)] {
+            grid.candidates[y][x] &= !1;
+        }
+
+        let step = x_wing(&grid).unwrap();
+        let rules = step
+            .houses
+            .iter()
+            .map(|instance| (instance.rule.as_str(), instance.index))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            vec![
+                ("extraRegions", 0),
+                ("extraRegions", 1),
+                ("row", 0),
+                ("row", 1)
+            ]
+        );
+        let eliminations = [(0, 1), (0, 2), (1, 0), (1, 3)]
+            .into_iter()
+            .map(|(y, x)| Candidate {
+                cell: Pos { x, y },
+                digit: 1,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(step.eliminations, eliminations);
+    }
+}
@@ -0,0 +1,47 @@
+use super::{bits, Grid, Step, Technique};
+
+/// Pointing and claiming: if all positions of a digit in a house lie in another house, the digit
+/// can be removed from the rest of the other house.
+pub(super) fn locked_candidates(grid: &Grid) -> Option<Step> {
+    for house in &grid.houses {
+        let Some(house_digits) = house.digits else {
+            continue;
+        };
+        for i in bits(house_digits) {
+            let positions = grid.positions(house, i);
+            if positions.len() < 2 {
+                continue;
+            }
+
+            let (y0, x0) = positions[0];
+            for &other in grid.houses_of(y0, x0) {
+                let other = &grid.houses[other];
+                if !positions.iter().all(|cell| other.cells.contains(cell)) {
+                    continue;
+                }
+                let eliminations = grid
+                    .positions(other, i)
+                    .into_iter()
+                    .filter(|cell| !house.cells.contains(cell))
+                    .map(|(y, x)| grid.candidate(y, x, i))
+                    .collect::<Vec<_>>();
+                if eliminations.is_empty() {
+                    continue;
+                }
+
+                return Some(Step {
+                    technique: Technique::LockedCandidates,
+                    houses: vec![house.instance.clone(), other.instance.clone()],
+                    candidates: positions
+                        .iter()
+                        .map(|&(y, x)| grid.candidate(y, x, i))
+                        .collect(),
+                    links: vec![],
+                    placements: vec![],
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,89 @@
+use super::{bits, ConstraintKind, Grid, Mask, Step, Technique};
+
+/// Candidates of a thermo cell which are not greater than every candidate of the previous cell
+/// or not less than every candidate of the next one.
+pub(super) fn thermo_range(grid: &Grid) -> Option<Step> {
+    for constraint in &grid.constraints {
+        let ConstraintKind::Thermo(cells) = &constraint.kind else {
+            continue;
+        };
+
+        // The digits are sorted, so comparing digit indices compares digits.
+        let mut eliminated = vec![vec![]; cells.len()];
+        let mut lowest: Option<usize> = None;
+        for (k, &(y, x)) in cells.iter().enumerate() {
+            let allowed = lowest.map_or(Mask::MAX, |i| {
+                Mask::MAX.checked_shl(i as u32 + 1).unwrap_or(0)
+            });
+            eliminated[k].extend(bits(grid.candidates[y][x] & !allowed));
+            let options = grid.options(y, x) & allowed;
+            if options == 0 {
+                break;
+            }
+            lowest = Some(options.trailing_zeros() as usize);
+        }
+        let mut highest: Option<usize> = None;
+        for (k, &(y, x)) in cells.iter().enumerate().rev() {
+            let allowed = highest.map_or(Mask::MAX, |i| (1 << i) - 1);
+            eliminated[k].extend(bits(grid.candidates[y][x] & !allowed));
+            let options = grid.options(y, x) & allowed;
+            if options == 0 {
+                break;
+            }
+            highest = Some(Mask::BITS as usize - 1 - options.leading_zeros() as usize);
+        }
+
+        let mut eliminations = vec![];
+        for (k, &(y, x)) in cells.iter().enumerate() {
+            eliminated[k].sort();
+            eliminated[k].dedup();
+            eliminations.extend(eliminated[k].iter().map(|&i| grid.candidate(y, x, i)));
+        }
+        if eliminations.is_empty() {
+            continue;
+        }
+
+        return Some(Step {
+            technique: Technique::ThermoRange,
+            houses: vec![constraint.instance.clone()],
+            candidates: vec![],
+            links: vec![],
+            placements: vec![],
+            eliminations,
+        });
+    }
+    None
+}
+
+/// Candidates of a palindrome cell which the mirrored cell does not have.
+pub(super) fn palindrome_equality(grid: &Grid) -> Option<Step> {
+    for constraint in &grid.constraints {
+        let ConstraintKind::Palindrome(cells) = &constraint.kind else {
+            continue;
+        };
+
+        let mut eliminations = vec![];
+        for (&(y, x), &(y2, x2)) in cells.iter().zip(cells.iter().rev()) {
+            if (y, x) == (y2, x2) {
+                continue;
+            }
+            let options = grid.options(y2, x2);
+            for i in bits(grid.candidates[y][x] & !options) {
+                eliminations.push(grid.candidate(y, x, i));
+            }
+        }
+        if eliminations.is_empty() {
+            continue;
+        }
+
+        return Some(Step {
+            technique: Technique::PalindromeEquality,
+            houses: vec![constraint.instance.clone()],
+            candidates: vec![],
+            links: vec![],
+            placements: vec![],
+            eliminations,
+        });
+    }
+    None
+}
@@ -0,0 +1,949 @@
+//! A solver applying human techniques, which reports how each deduction is made.
+//!
+//! Besides the houses of the puzzle (rows, columns, blocks, diagonals, extra regions and
+//! killer cages with distinct digits), the techniques understand killer sums, arrows, thermos,
+//! XV and consecutive borders and palindromes. Other rules are not understood, so the solver
+//! may get stuck on variant puzzles which `irrefutable_facts` solves.
+
+mod chains;
+mod fish;
+mod intersections;
+mod lines;
+mod pairs;
+mod singles;
+mod subsets;
+mod sums;
+
+use serde::Serialize;
+
+use crate::error::SolverError;
+use crate::puzzle::{Candidate, Pos, Puzzle};
+use crate::puzzle::{XV_NO_CONSTRAINT, XV_V, XV_X};
+use crate::solver::{
+    block_regions, border_index, check_cell, check_grid, is_consecutive, RuleInstance,
+};
+
+/// The human techniques, from the easiest to the hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    PairRestriction,
+    PalindromeEquality,
+    ThermoRange,
+    CageCombination,
+    LockedCandidates,
+    ArrowSum,
+    InniesOuties,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    XWing,
+    NakedQuad,
+    HiddenQuad,
+    Swordfish,
+    Jellyfish,
+    #[serde(rename = "xyChain")]
+    XYChain,
+    AlternatingInferenceChain,
+}
+
+/// A single deduction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Step {
+    pub technique: Technique,
+
+    /// The houses and other rule instances the deduction is based on. For fish, the base houses
+    /// come first, followed by the cover houses. For innies and outies, the house comes first,
+    /// followed by the cages.
+    pub houses: Vec<RuleInstance>,
+
+    /// The candidates the deduction is based on. For chains, the candidates of the chain in
+    /// order.
+    pub candidates: Vec<Candidate>,
+
+    /// The links between consecutive candidates of a chain. Empty for other techniques.
+    pub links: Vec<Link>,
+
+    pub placements: Vec<Candidate>,
+
+    /// The removed candidates, including those removed from the houses of placed digits.
+    pub eliminations: Vec<Candidate>,
+}
+
+/// An inference between two candidates of a chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Link {
+    pub from: Candidate,
+    pub to: Candidate,
+
+    /// Whether at least one of the candidates is true (a strong link). Otherwise, at most one
+    /// of them is (a weak link).
+    pub strong: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogicResult {
+    /// The deductions in the order they were made.
+    pub steps: Vec<Step>,
+
+    /// The digits of the cells filled by the givens or the steps.
+    #[serde(rename = "decidedNumbers")]
+    pub decided_nums: Vec<Vec<Option<i32>>>,
+
+    /// Whether all cells have been filled. If not, no technique applied anymore.
+    pub solved: bool,
+}
+
+/// The keys of the rules the techniques understand.
+const SUPPORTED_RULES: &[&str] = &[
+    "givenNumbers",
+    "blocks",
+    "diagonal",
+    "extraRegions",
+    "killer",
+    "arrow",
+    "thermo",
+    "xv",
+    "consecutive",
+    "palindrome",
+];
+
+/// Returns the keys of the rules of `puzzle` which the techniques do not understand. A grid of
+/// such a puzzle keeps candidates which the ignored rules rule out.
+pub fn unsupported_rules(puzzle: &Puzzle) -> Vec<&str> {
+    puzzle
+        .rules()
+        .into_iter()
+        .map(|rule| rule.key())
+        .filter(|key| !SUPPORTED_RULES.contains(key))
+        .collect()
+}
+
+/// Solves `puzzle` step by step, always applying the easiest technique which makes progress.
+pub fn solve_logically(puzzle: &Puzzle) -> Result<LogicResult, SolverError> {
+    let mut grid = Grid::new(puzzle)?;
+    let steps = grid.solve();
+
+    let decided_nums = grid
+        .values
+        .iter()
+        .map(|row| row.iter().map(|v| v.map(|i| grid.digits[i])).collect())
+        .collect::<Vec<Vec<_>>>();
+    let solved = decided_nums.iter().flatten().all(|v| v.is_some());
+    Ok(LogicResult {
+        steps,
+        decided_nums,
+        solved,
+    })
+}
+
+type Finder = fn(&Grid) -> Option<Step>;
+
+/// The finders of all techniques, in the order of `Technique`.
+const FINDERS: &[Finder] = &[
+    singles::hidden_single,
+    singles::naked_single,
+    pairs::pair_restriction,
+    lines::palindrome_equality,
+    lines::thermo_range,
+    sums::cage_combination,
+    intersections::locked_candidates,
+    sums::arrow_sum,
+    sums::innies_outies,
+    subsets::naked_pair,
+    subsets::hidden_pair,
+    subsets::naked_triple,
+    subsets::hidden_triple,
+    fish::x_wing,
+    subsets::naked_quad,
+    subsets::hidden_quad,
+    fish::swordfish,
+    fish::jellyfish,
+    chains::xy_chain,
+    chains::alternating_inference_chain,
+];
+
+/// The finders of the techniques which look at a single house or constraint at a time, like
+/// the unit propagation of a SAT solver.
+const PROPAGATORS: &[Finder] = &[
+    singles::hidden_single,
+    singles::naked_single,
+    pairs::pair_restriction,
+    lines::palindrome_equality,
+    lines::thermo_range,
+    sums::cage_combination,
+    sums::arrow_sum,
+];
+
+/// A set of digits, given by their indices in `Grid::digits`.
+pub(crate) type Mask = u64;
+
+/// A group of cells which must contain distinct digits.
+#[derive(Debug, Clone)]
+pub(crate) struct House {
+    pub instance: RuleInstance,
+    pub cells: Vec<(usize, usize)>,
+
+    /// If the cells must contain each of a set of digits exactly once (and nothing else), that
+    /// set. `None` if the cells only need to be different.
+    pub digits: Option<Mask>,
+}
+
+/// A rule instance other than a house which the techniques understand.
+#[derive(Debug, Clone)]
+pub(crate) struct Constraint {
+    pub instance: RuleInstance,
+    pub kind: ConstraintKind,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum ConstraintKind {
+    /// The digits of the cells sum to the value (a killer cage).
+    Sum(Vec<(usize, usize)>, i32),
+
+    /// The digit of the first cell is the sum of the digits of the others.
+    Arrow(Vec<(usize, usize)>),
+
+    /// The digits strictly increase along the cells.
+    Thermo(Vec<(usize, usize)>),
+
+    /// The digits of two cells are in a relation.
+    Pair((usize, usize), (usize, usize), Relation),
+
+    /// The digits read the same in both directions.
+    Palindrome(Vec<(usize, usize)>),
+}
+
+/// A relation between the digits of two cells.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Relation {
+    /// The digits sum to the value.
+    SumIs(i32),
+
+    /// The digits sum to neither of the values.
+    SumIsNeither(i32, i32),
+
+    Consecutive,
+    NotConsecutive,
+}
+
+impl Relation {
+    pub fn allows(self, a: i32, b: i32) -> bool {
+        match self {
+            Relation::SumIs(sum) => a + b == sum,
+            Relation::SumIsNeither(s, t) => a + b != s && a + b != t,
+            Relation::Consecutive => is_consecutive(a, b),
+            Relation::NotConsecutive => !is_consecutive(a, b),
+        }
+    }
+}
+
+impl ConstraintKind {
+    /// Whether `values`, the digits of the cells of the constraint in order, satisfy it.
+    fn is_satisfied_by(&self, values: &[i32]) -> bool {
+        match self {
+            ConstraintKind::Sum(_, sum) => values.iter().sum::<i32>() == *sum,
+            ConstraintKind::Arrow(_) => values[0] == values[1..].iter().sum::<i32>(),
+            ConstraintKind::Thermo(_) => values.windows(2).all(|pair| pair[0] < pair[1]),
+            ConstraintKind::Pair(_, _, relation) => relation.allows(values[0], values[1]),
+            ConstraintKind::Palindrome(_) => values.iter().eq(values.iter().rev()),
+        }
+    }
+
+    fn cells(&self) -> Vec<(usize, usize)> {
+        match self {
+            ConstraintKind::Sum(cells, _)
+            | ConstraintKind::Arrow(cells)
+            | ConstraintKind::Thermo(cells)
+            | ConstraintKind::Palindrome(cells) => cells.clone(),
+            ConstraintKind::Pair(a, b, _) => vec![*a, *b],
+        }
+    }
+}
+
+/// The filled cells and candidates of a `Grid`, from which trial placements are undone without
+/// cloning its houses and constraints.
+pub(crate) struct Snapshot {
+    candidates: Vec<Vec<Mask>>,
+    values: Vec<Vec<Option<usize>>>,
+}
+
+/// The state of a logical solve: the filled cells and the candidates of the others.
+#[derive(Clone)]
+pub(crate) struct Grid {
+    pub digits: Vec<i32>,
+    pub houses: Vec<House>,
+    pub constraints: Vec<Constraint>,
+
+    /// The indices of the houses containing each cell.
+    cell_houses: Vec<Vec<Vec<usize>>>,
+
+    /// The candidates of each empty cell; 0 for filled cells.
+    pub candidates: Vec<Vec<Mask>>,
+
+    /// The digit (as an index) of each filled cell.
+    pub values: Vec<Vec<Option<usize>>>,
+}
+
+impl Grid {
+    /// Builds the grid of `puzzle` with its givens placed. Rejects houses the techniques cannot
+    /// model (see `collect_houses`) and givens repeating a digit within a house.
+    pub fn new(puzzle: &Puzzle) -> Result<Grid, SolverError> {
+        let (h, w) = (puzzle.height(), puzzle.width());
+        if h == 0 || w == 0 {
+            return Err(SolverError::unsupported_value(
+                "size",
+                "board size must be positive",
+            ));
+        }
+        check_grid(
+            "givenNumbers",
+            "numbers",
+            &puzzle.given_numbers.numbers,
+            h,
+            w,
+        )?;
+        let digits = puzzle.digits();
+        if digits.len() > Mask::BITS as usize {
+            return Err(SolverError::unsupported_value(
+                "digits",
+                format!("the logical solver supports at most {} digits", Mask::BITS),
+            ));
+        }
+
+        let houses = collect_houses(puzzle, &digits)?;
+        let constraints = collect_constraints(puzzle)?;
+        let mut cell_houses = vec![vec![vec![]; w]; h];
+        for (i, house) in houses.iter().enumerate() {
+            for &(y, x) in &house.cells {
+                cell_houses[y][x].push(i);
+            }
+        }
+
+        let all = mask_of(0..digits.len());
+        let mut grid = Grid {
+            digits,
+            houses,
+            constraints,
+            cell_houses,
+            candidates: vec![vec![all; w]; h],
+            values: vec![vec![None; w]; h],
+        };
+
+        for house in &grid.houses {
+            if let Some(house_digits) = house.digits {
+                for &(y, x) in &house.cells {
+                    grid.candidates[y][x] &= house_digits;
+                }
+            }
+        }
+
+        for y in 0..h {
+            for x in 0..w {
+                let Some(n) = puzzle.given_numbers.numbers[y][x] else {
+                    continue;
+                };
+                let Some(i) = grid.digit_index(n) else {
+                    return Err(SolverError::unsupported_value(
+                        "givenNumbers",
+                        format!("{} is not a digit of the puzzle", n),
+                    ));
+                };
+                if let Some(&h) = grid
+                    .houses_of(y, x)
+                    .iter()
+                    .find(|&&h| grid.is_placed(&grid.houses[h], i))
+                {
+                    let instance = &grid.houses[h].instance;
+                    return Err(SolverError::unsupported_value(
+                        "givenNumbers",
+                        format!(
+                            "{} is given twice in {} {}",
+                            n, instance.rule, instance.index
+                        ),
+                    ));
+                }
+                grid.place(y, x, i);
+            }
+        }
+
+        Ok(grid)
+    }
+
+    pub fn height(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.values[0].len()
+    }
+
+    fn digit_index(&self, digit: i32) -> Option<usize> {
+        self.digits.iter().position(|&d| d == digit)
+    }
+
+    pub fn has_candidate(&self, y: usize, x: usize, i: usize) -> bool {
+        (self.candidates[y][x] >> i) & 1 != 0
+    }
+
+    /// Returns the candidate of digit index `i` at (y, x).
+    pub fn candidate(&self, y: usize, x: usize, i: usize) -> Candidate {
+        Candidate {
+            cell: Pos { x, y },
+            digit: self.digits[i],
+        }
+    }
+
+    /// Returns the candidates of (y, x), or its digit if it is filled.
+    pub fn options(&self, y: usize, x: usize) -> Mask {
+        match self.values[y][x] {
+            Some(i) => 1 << i,
+            None => self.candidates[y][x],
+        }
+    }
+
+    /// Whether two different cells share a house, so that their digits must differ.
+    pub fn sees(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        a != b
+            && self
+                .houses_of(a.0, a.1)
+                .iter()
+                .any(|&h| self.houses[h].cells.contains(&b))
+    }
+
+    /// Returns the indices of the houses containing (y, x).
+    pub fn houses_of(&self, y: usize, x: usize) -> &[usize] {
+        &self.cell_houses[y][x]
+    }
+
+    /// Returns the empty cells of `house` which have digit index `i` as a candidate.
+    pub fn positions(&self, house: &House, i: usize) -> Vec<(usize, usize)> {
+        house
+            .cells
+            .iter()
+            .copied()
+            .filter(|&(y, x)| self.has_candidate(y, x, i))
+            .collect()
+    }
+
+    /// Whether digit index `i` is already placed in `house`.
+    pub fn is_placed(&self, house: &House, i: usize) -> bool {
+        house
+            .cells
+            .iter()
+            .any(|&(y, x)| self.values[y][x] == Some(i))
+    }
+
+    /// Whether an empty cell has no candidates, a digit has no place left in a house or the
+    /// filled cells of a constraint violate it.
+    pub fn is_contradictory(&self) -> bool {
+        let h = self.height();
+        let w = self.width();
+        if (0..h).any(|y| (0..w).any(|x| self.values[y][x].is_none() && self.candidates[y][x] == 0))
+        {
+            return true;
+        }
+        let house_contradictory = self.houses.iter().any(|house| {
+            house.digits.is_some_and(|digits| {
+                bits(digits)
+                    .any(|i| !self.is_placed(house, i) && self.positions(house, i).is_empty())
+            })
+        });
+        house_contradictory
+            || self.constraints.iter().any(|constraint| {
+                let values = constraint
+                    .kind
+                    .cells()
+                    .iter()
+                    .map(|&(y, x)| self.values[y][x].map(|i| self.digits[i]))
+                    .collect::<Option<Vec<_>>>();
+                values.is_some_and(|values| !constraint.kind.is_satisfied_by(&values))
+            })
+    }
+
+    /// Whether all cells are filled.
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().flatten().all(|v| v.is_some())
+    }
+
+    /// Applies the easiest technique which makes progress until none does or the grid becomes
+    /// contradictory. Returns the steps made.
+    pub fn solve(&mut self) -> Vec<Step> {
+        self.apply_finders(FINDERS)
+    }
+
+    /// Like `solve`, but only applies the techniques looking at a single house or constraint.
+    pub fn propagate(&mut self) -> Vec<Step> {
+        self.apply_finders(PROPAGATORS)
+    }
+
+    fn apply_finders(&mut self, finders: &[Finder]) -> Vec<Step> {
+        let mut steps = vec![];
+        while !self.is_contradictory() {
+            let Some(mut step) = finders.iter().find_map(|finder| finder(self)) else {
+                break;
+            };
+            self.apply(&mut step);
+            steps.push(step);
+        }
+        steps
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            candidates: self.candidates.clone(),
+            values: self.values.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.candidates = snapshot.candidates;
+        self.values = snapshot.values;
+    }
+
+    pub fn eliminate(&mut self, y: usize, x: usize, i: usize) {
+        self.candidates[y][x] &= !(1 << i);
+    }
+
+    /// Places digit index `i` at (y, x) and removes it from the other cells of its houses.
+    /// Returns the removed candidates.
+    pub fn place(&mut self, y: usize, x: usize, i: usize) -> Vec<Candidate> {
+        self.values[y][x] = Some(i);
+        self.candidates[y][x] = 0;
+
+        let mut eliminations = vec![];
+        for &h in &self.cell_houses[y][x] {
+            for &(y2, x2) in &self.houses[h].cells {
+                if self.has_candidate(y2, x2, i) {
+                    self.candidates[y2][x2] &= !(1 << i);
+                    eliminations.push(Candidate {
+                        cell: Pos { x: x2, y: y2 },
+                        digit: self.digits[i],
+                    });
+                }
+            }
+        }
+        eliminations
+    }
+
+    /// Applies the placements and eliminations of `step`, adding the candidates removed by the
+    /// placements to its eliminations.
+    fn apply(&mut self, step: &mut Step) {
+        for elimination in &step.eliminations {
+            let Pos { x, y } = elimination.cell;
+            if let Some(i) = self.digit_index(elimination.digit) {
+                self.eliminate(y, x, i);
+            }
+        }
+        for placement in step.placements.clone() {
+            let Pos { x, y } = placement.cell;
+            if let Some(i) = self.digit_index(placement.digit) {
+                let eliminations = self.place(y, x, i);
+                step.eliminations.extend(eliminations);
+            }
+        }
+    }
+}
+
+pub(crate) fn mask_of(indices: impl IntoIterator<Item = usize>) -> Mask {
+    indices.into_iter().fold(0, |mask, i| mask | (1 << i))
+}
+
+/// Iterates over the indices set in `mask`.
+pub(crate) fn bits(mask: Mask) -> impl Iterator<Item = usize> {
+    (0..Mask::BITS as usize).filter(move |&i| (mask >> i) & 1 != 0)
+}
+
+/// Returns all `k`-element subsets of `items`, in lexicographic order.
+pub(crate) fn combinations<T: Copy>(items: &[T], k: usize) -> Vec<Vec<T>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    let mut res = vec![];
+    for i in 0..items.len() {
+        for mut rest in combinations(&items[i + 1..], k - 1) {
+            rest.insert(0, items[i]);
+            res.push(rest);
+        }
+    }
+    res
+}
+
+/// Collects the houses of `puzzle` in the order in which `build_model` numbers them.
+///
+/// A house whose required digits (given in ascending order) contain duplicates, or which has
+/// more cells than required digits, is rejected with an `UnsupportedValue` error, as the
+/// techniques need each digit to appear at most once.
+fn collect_houses(puzzle: &Puzzle, digits: &[i32]) -> Result<Vec<House>, SolverError> {
+    let (h, w) = (puzzle.height(), puzzle.width());
+    let house_digits = puzzle.house_digits();
+    let house_digits = house_digits.as_slice();
+
+    let mut houses = vec![];
+    let mut add = |rule: &str,
+                   index: usize,
+                   cells: Vec<(usize, usize)>,
+                   required: Option<&[i32]>|
+     -> Result<(), SolverError> {
+        // The digits come from `houseDigits` unless an extra region has its own.
+        let key = match required {
+            Some(required) if rule == "extraRegions" && required != house_digits => rule,
+            _ => "houseDigits",
+        };
+        let digits = match required {
+            Some(required) if required.windows(2).any(|pair| pair[0] == pair[1]) => {
+                return Err(SolverError::unsupported_value(
+                    key,
+                    format!(
+                        "the logical solver does not support repeated digits in {} {}",
+                        rule, index
+                    ),
+                ));
+            }
+            Some(required) if cells.len() > required.len() => {
+                return Err(SolverError::unsupported_value(
+                    key,
+                    format!(
+                        "the logical solver does not support more cells than digits in {} {}",
+                        rule, index
+                    ),
+                ));
+            }
+            Some(required) if cells.len() == required.len() => Some(mask_of(
+                digits
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, d)| required.contains(d))
+                    .map(|(i, _)| i),
+            )),
+            // Fewer cells than required digits: each digit appears at most once.
+            _ => None,
+        };
+        houses.push(House {
+            instance: RuleInstance {
+                rule: rule.to_string(),
+                index,
+                cells: cells.iter().map(|&(y, x)| Pos { x, y }).collect(),
+            },
+            cells,
+            digits,
+        });
+        Ok(())
+    };
+
+    for y in 0..h {
+        add(
+            "row",
+            y,
+            (0..w).map(|x| (y, x)).collect(),
+            Some(house_digits),
+        )?;
+    }
+    for x in 0..w {
+        add(
+            "column",
+            x,
+            (0..h).map(|y| (y, x)).collect(),
+            Some(house_digits),
+        )?;
+    }
+    if let Some(blocks) = &puzzle.blocks {
+        for (i, cells) in block_regions(blocks, h, w, house_digits.len())?
+            .into_iter()
+            .enumerate()
+        {
+            add("blocks", i, cells, Some(house_digits))?;
+        }
+    }
+    if let Some(diagonal) = &puzzle.diagonal {
+        if h == w && diagonal.main_diagonal {
+            add(
+                "diagonal",
+                0,
+                (0..h).map(|i| (i, i)).collect(),
+                Some(house_digits),
+            )?;
+        }
+        if h == w && diagonal.anti_diagonal {
+            add(
+                "diagonal",
+                1,
+                (0..h).map(|i| (i, h - 1 - i)).collect(),
+                Some(house_digits),
+            )?;
+        }
+    }
+    if let Some(extra_regions) = &puzzle.extra_regions {
+        for (i, region) in extra_regions.regions.iter().enumerate() {
+            let cells = region
+                .cells
+                .iter()
+                .filter(|pos| pos.y < h && pos.x < w)
+                .map(|pos| (pos.y, pos.x))
+                .collect::<Vec<_>>();
+            match &region.digits {
+                Some(required) => {
+                    let mut required = required.clone();
+                    required.sort();
+                    add("extraRegions", i, cells, Some(&required))?;
+                }
+                None if cells.len() == house_digits.len() => {
+                    add("extraRegions", i, cells, Some(house_digits))?
+                }
+                None => add("extraRegions", i, cells, None)?,
+            }
+        }
+    }
+    if let Some(killer) = &puzzle.killer {
+        if killer.distinct {
+            for (i, region) in killer.regions.iter().enumerate() {
+                add("killer", i, cells_of("killer", &region.cells, h, w)?, None)?;
+            }
+        }
+    }
+
+    Ok(houses)
+}
+
+/// Collects the rule instances of `puzzle` other than houses which the techniques understand.
+fn collect_constraints(puzzle: &Puzzle) -> Result<Vec<Constraint>, SolverError> {
+    let (h, w) = (puzzle.height(), puzzle.width());
+
+    let mut constraints = vec![];
+    let mut add = |rule: &str, index: usize, cells: Vec<Pos>, kind: ConstraintKind| {
+        constraints.push(Constraint {
+            instance: RuleInstance {
+                rule: rule.to_string(),
+                index,
+                cells,
+            },
+            kind,
+        });
+    };
+
+    if let Some(killer) = &puzzle.killer {
+        for (i, region) in killer.regions.iter().enumerate() {
+            if let Some(sum) = region.sum {
+                let cells = cells_of("killer", &region.cells, h, w)?;
+                add(
+                    "killer",
+                    i,
+                    region.cells.clone(),
+                    ConstraintKind::Sum(cells, sum),
+                );
+            }
+        }
+    }
+    if let Some(arrow) = &puzzle.arrow {
+        for (i, cells) in arrow.arrows.iter().enumerate() {
+            if cells.is_empty() {
+                return Err(SolverError::unsupported_value(
+                    "arrow",
+                    "an arrow must have at least one cell",
+                ));
+            }
+            let kind = ConstraintKind::Arrow(cells_of("arrow", cells, h, w)?);
+            add("arrow", i, cells.clone(), kind);
+        }
+    }
+    if let Some(thermo) = &puzzle.thermo {
+        for (i, cells) in thermo.thermos.iter().enumerate() {
+            let kind = ConstraintKind::Thermo(cells_of("thermo", cells, h, w)?);
+            add("thermo", i, cells.clone(), kind);
+        }
+    }
+    if let Some(xv) = &puzzle.xv {
+        check_grid("xv", "horizontalBorder", &xv.horizontal, h - 1, w)?;
+        check_grid("xv", "verticalBorder", &xv.vertical, h, w - 1)?;
+        for (a, b, vertical) in borders(h, w) {
+            let kind = if vertical {
+                xv.vertical[a.0][a.1]
+            } else {
+                xv.horizontal[a.0][a.1]
+            };
+            let relation = match kind {
+                XV_X => Relation::SumIs(10),
+                XV_V => Relation::SumIs(5),
+                XV_NO_CONSTRAINT if xv.all_shown => Relation::SumIsNeither(10, 5),
+                XV_NO_CONSTRAINT => continue,
+                _ => {
+                    return Err(SolverError::unsupported_value(
+                        "xv",
+                        format!("unknown XV kind {}", kind),
+                    ))
+                }
+            };
+            let index = border_index(a.0, a.1, vertical, h, w);
+            add(
+                "xv",
+                index,
+                to_pos(&[a, b]),
+                ConstraintKind::Pair(a, b, relation),
+            );
+        }
+    }
+    if let Some(consecutive) = &puzzle.consecutive {
+        check_grid(
+            "consecutive",
+            "horizontalBorder",
+            &consecutive.horizontal,
+            h - 1,
+            w,
+        )?;
+        check_grid(
+            "consecutive",
+            "verticalBorder",
+            &consecutive.vertical,
+            h,
+            w - 1,
+        )?;
+        for (a, b, vertical) in borders(h, w) {
+            let marked = if vertical {
+                consecutive.vertical[a.0][a.1]
+            } else {
+                consecutive.horizontal[a.0][a.1]
+            };
+            let relation = if marked {
+                Relation::Consecutive
+            } else if consecutive.all_shown {
+                Relation::NotConsecutive
+            } else {
+                continue;
+            };
+            let index = border_index(a.0, a.1, vertical, h, w);
+            add(
+                "consecutive",
+                index,
+                to_pos(&[a, b]),
+                ConstraintKind::Pair(a, b, relation),
+            );
+        }
+    }
+    if let Some(palindrome) = &puzzle.palindrome {
+        for (i, cells) in palindrome.palindromes.iter().enumerate() {
+            let kind = ConstraintKind::Palindrome(cells_of("palindrome", cells, h, w)?);
+            add("palindrome", i, cells.clone(), kind);
+        }
+    }
+
+    Ok(constraints)
+}
+
+fn cells_of(
+    rule: &str,
+    cells: &[Pos],
+    h: usize,
+    w: usize,
+) -> Result<Vec<(usize, usize)>, SolverError> {
+    cells
+        .iter()
+        .map(|pos| check_cell(rule, pos, h, w))
+        .collect()
+}
+
+fn to_pos(cells: &[(usize, usize)]) -> Vec<Pos> {
+    cells.iter().map(|&(y, x)| Pos { x, y }).collect()
+}
+
+type Cell = (usize, usize);
+
+/// Returns the pairs of adjacent cells of an `h` x `w` board, together with whether they are
+/// separated by a vertical border, in the order of `border_index`.
+fn borders(h: usize, w: usize) -> Vec<(Cell, Cell, bool)> {
+    let horizontal = (0..h - 1).flat_map(|y| (0..w).map(move |x| ((y, x), (y + 1, x), false)));
+    let vertical = (0..h).flat_map(|y| (0..w - 1).map(move |x| ((y, x), (y, x + 1), true)));
+    horizontal.chain(vertical).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn sudoku_4x4(numbers: Value) -> Value {
+        json!({
+            "size": 4,
+            "givenNumbers": { "numbers": numbers },
+            "blocks": {
+                "horizontalBorder": [
+                    [false, false, false, false],
+                    [true, true, true, true],
+                    [false, false, false, false]
+                ],
+                "verticalBorder": vec![[false, true, false]; 4]
+            }
+        })
+    }
+
+    #[test]
+    fn singles_solve_an_easy_puzzle() {
+        let puzzle: Puzzle = serde_json::from_value(sudoku_4x4(json!([
+            [1, 2, null, 4],
+            [null, 4, 1, null],
+            [null, 1, 4, null],
+            [4, null, null, 1]
+        ])))
+        .unwrap();
+
+        let result = solve_logically(&puzzle).unwrap();
+        assert!(result.solved);
+        assert_eq!(
+            result.decided_nums,
+            [[1, 2, 3, 4], [3, 4, 1, 2], [2, 1, 4, 3], [4, 3, 2, 1]]
+                .map(|row| row.map(Some).to_vec())
+                .to_vec()
+        );
+        assert!(result.steps.iter().all(|step| matches!(
+            step.technique,
+            Technique::HiddenSingle | Technique::NakedSingle
+        )));
+    }
+
+    #[test]
+    fn repeated_house_digits_are_rejected() {
+        let mut puzzle_json = sudoku_4x4(json!(vec![vec![None::<i32>; 4]; 4]));
+        puzzle_json["houseDigits"] = json!([1, 1, 2, 3]);
+        let puzzle: Puzzle = serde_json::from_value(puzzle_json).unwrap();
+
+        let err = Grid::new(&puzzle).err().unwrap();
+        assert_eq!(err.code, ErrorCode::UnsupportedValue);
+        assert_eq!(err.rule.as_deref(), Some("houseDigits"));
+    }
+
+    #[test]
+    fn houses_with_more_cells_than_digits_are_rejected() {
+        let puzzle: Puzzle = serde_json::from_value(json!({
+            "size": 0,
+            "height": 2,
+            "width": 3,
+            "digits": [1, 2],
+            "givenNumbers": { "numbers": vec![vec![None::<i32>; 3]; 2] }
+        }))
+        .unwrap();
+
+        let err = Grid::new(&puzzle).err().unwrap();
+        assert_eq!(err.code, ErrorCode::UnsupportedValue);
+        assert_eq!(err.rule.as_deref(), Some("houseDigits"));
+    }
+
+    #[test]
+    fn repeated_givens_are_rejected() {
+        let puzzle: Puzzle = serde_json::from_value(sudoku_4x4(json!([
+            [1, null, null, null],
+            [null, null, null, null],
+            [1, null, null, null],
+            [null, null, null, null]
+        ])))
+        .unwrap();
+
+        let err = Grid::new(&puzzle).err().unwrap();
+        assert_eq!(err.code, ErrorCode::UnsupportedValue);
+        assert_eq!(err.rule.as_deref(), Some("givenNumbers"));
+        assert_eq!(err.message, "1 is given twice in column 0");
+    }
+}
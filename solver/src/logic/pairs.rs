@@ -0,0 +1,37 @@
+use super::{bits, ConstraintKind, Grid, Step, Technique};
+
+/// A candidate of a cell on an XV or consecutive border which no digit of the other cell is in
+/// the required relation with.
+pub(super) fn pair_restriction(grid: &Grid) -> Option<Step> {
+    for constraint in &grid.constraints {
+        let ConstraintKind::Pair(a, b, relation) = constraint.kind else {
+            continue;
+        };
+        let distinct = grid.sees(a, b);
+
+        let mut eliminations = vec![];
+        for (cell, other) in [(a, b), (b, a)] {
+            for i in bits(grid.candidates[cell.0][cell.1]) {
+                let supported = bits(grid.options(other.0, other.1)).any(|j| {
+                    (!distinct || i != j) && relation.allows(grid.digits[i], grid.digits[j])
+                });
+                if !supported {
+                    eliminations.push(grid.candidate(cell.0, cell.1, i));
+                }
+            }
+        }
+        if eliminations.is_empty() {
+            continue;
+        }
+
+        return Some(Step {
+            technique: Technique::PairRestriction,
+            houses: vec![constraint.instance.clone()],
+            candidates: vec![],
+            links: vec![],
+            placements: vec![],
+            eliminations,
+        });
+    }
+    None
+}
@@ -0,0 +1,48 @@
+use super::{bits, Grid, Step, Technique};
+
+/// A digit which has a single possible position in a house.
+pub(super) fn hidden_single(grid: &Grid) -> Option<Step> {
+    for house in &grid.houses {
+        let Some(house_digits) = house.digits else {
+            continue;
+        };
+        for i in bits(house_digits) {
+            let positions = grid.positions(house, i);
+            if positions.len() != 1 || grid.is_placed(house, i) {
+                continue;
+            }
+            let (y, x) = positions[0];
+            return Some(Step {
+                technique: Technique::HiddenSingle,
+                houses: vec![house.instance.clone()],
+                candidates: vec![grid.candidate(y, x, i)],
+                links: vec![],
+                placements: vec![grid.candidate(y, x, i)],
+                eliminations: vec![],
+            });
+        }
+    }
+    None
+}
+
+/// A cell with a single candidate.
+pub(super) fn naked_single(grid: &Grid) -> Option<Step> {
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            let candidates = grid.candidates[y][x];
+            if candidates.count_ones() != 1 {
+                continue;
+            }
+            let i = candidates.trailing_zeros() as usize;
+            return Some(Step {
+                technique: Technique::NakedSingle,
+                houses: vec![],
+                candidates: vec![grid.candidate(y, x, i)],
+                links: vec![],
+                placements: vec![grid.candidate(y, x, i)],
+                eliminations: vec![],
+            });
+        }
+    }
+    None
+}
@@ -0,0 +1,126 @@
+use super::{bits, combinations, mask_of, Grid, Mask, Step, Technique};
+
+pub(super) fn naked_pair(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 2, Technique::NakedPair)
+}
+
+pub(super) fn naked_triple(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 3, Technique::NakedTriple)
+}
+
+pub(super) fn naked_quad(grid: &Grid) -> Option<Step> {
+    naked_subset(grid, 4, Technique::NakedQuad)
+}
+
+pub(super) fn hidden_pair(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 2, Technique::HiddenPair)
+}
+
+pub(super) fn hidden_triple(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 3, Technique::HiddenTriple)
+}
+
+pub(super) fn hidden_quad(grid: &Grid) -> Option<Step> {
+    hidden_subset(grid, 4, Technique::HiddenQuad)
+}
+
+/// `size` cells of a house with only `size` candidates in total: these digits can be removed
+/// from the other cells of the house.
+fn naked_subset(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    for house in &grid.houses {
+        let cells = house
+            .cells
+            .iter()
+            .copied()
+            .filter(|&(y, x)| (2..=size as u32).contains(&grid.candidates[y][x].count_ones()))
+            .collect::<Vec<_>>();
+
+        for subset in combinations(&cells, size) {
+            let digits = subset
+                .iter()
+                .fold(0 as Mask, |mask, &(y, x)| mask | grid.candidates[y][x]);
+            if digits.count_ones() as usize != size {
+                continue;
+            }
+
+            let mut eliminations = vec![];
+            for &(y, x) in &house.cells {
+                if subset.contains(&(y, x)) {
+                    continue;
+                }
+                for i in bits(grid.candidates[y][x] & digits) {
+                    eliminations.push(grid.candidate(y, x, i));
+                }
+            }
+            if eliminations.is_empty() {
+                continue;
+            }
+
+            return Some(Step {
+                technique,
+                houses: vec![house.instance.clone()],
+                candidates: subset
+                    .iter()
+                    .flat_map(|&(y, x)| bits(grid.candidates[y][x]).map(move |i| (y, x, i)))
+                    .map(|(y, x, i)| grid.candidate(y, x, i))
+                    .collect(),
+                links: vec![],
+                placements: vec![],
+                eliminations,
+            });
+        }
+    }
+    None
+}
+
+/// `size` digits of a house which can only go into the same `size` cells: the other candidates
+/// can be removed from these cells.
+fn hidden_subset(grid: &Grid, size: usize, technique: Technique) -> Option<Step> {
+    for house in &grid.houses {
+        let Some(house_digits) = house.digits else {
+            continue;
+        };
+        let digits = bits(house_digits)
+            .filter(|&i| (2..=size).contains(&grid.positions(house, i).len()))
+            .collect::<Vec<_>>();
+
+        for subset in combinations(&digits, size) {
+            let mut cells = subset
+                .iter()
+                .flat_map(|&i| grid.positions(house, i))
+                .collect::<Vec<_>>();
+            cells.sort();
+            cells.dedup();
+            if cells.len() != size {
+                continue;
+            }
+
+            let digits = mask_of(subset.iter().copied());
+            let mut eliminations = vec![];
+            for &(y, x) in &cells {
+                for i in bits(grid.candidates[y][x] & !digits) {
+                    eliminations.push(grid.candidate(y, x, i));
+                }
+            }
+            if eliminations.is_empty() {
+                continue;
+            }
+
+            return Some(Step {
+                technique,
+                houses: vec![house.instance.clone()],
+                candidates: cells
+                    .iter()
+                    .flat_map(|&(y, x)| {
+                        bits(grid.candidates[y][x] & digits).map(move |i| (y, x, i))
+                    })
+                    .map(|(y, x, i)| grid.candidate(y, x, i))
+                    .collect(),
+                links: vec![],
+                placements: vec![],
+                eliminations,
+            });
+        }
+    }
+    None
+}
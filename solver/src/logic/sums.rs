@@ -0,0 +1,273 @@
+use super::{bits, Constraint, ConstraintKind, Grid, Mask, Step, Technique};
+
+/// Candidates of a killer cage cell which appear in no combination of digits giving the sum of
+/// the cage.
+pub(super) fn cage_combination(grid: &Grid) -> Option<Step> {
+    for constraint in &grid.constraints {
+        let ConstraintKind::Sum(cells, sum) = &constraint.kind else {
+            continue;
+        };
+        let terms = cells.iter().map(|&cell| (cell, 1)).collect::<Vec<_>>();
+        if let Some(step) = sum_step(
+            grid,
+            &terms,
+            *sum,
+            Technique::CageCombination,
+            vec![constraint],
+        ) {
+            return Some(step);
+        }
+    }
+    None
+}
+
+/// Candidates of an arrow cell which appear in no assignment where the circle is the sum of
+/// the shaft.
+pub(super) fn arrow_sum(grid: &Grid) -> Option<Step> {
+    for constraint in &grid.constraints {
+        let ConstraintKind::Arrow(cells) = &constraint.kind else {
+            continue;
+        };
+        let terms = cells
+            .iter()
+            .enumerate()
+            .map(|(k, &cell)| (cell, if k == 0 { 1 } else { -1 }))
+            .collect::<Vec<_>>();
+        if let Some(step) = sum_step(grid, &terms, 0, Technique::ArrowSum, vec![constraint]) {
+            return Some(step);
+        }
+    }
+    None
+}
+
+/// The maximum number of innies or outies summed up. `supported` searches the assignments of
+/// the cells, which gets slow on larger groups, and a human would not add them up either.
+const MAX_INNIES_OUTIES: usize = 5;
+
+/// The 45 rule: the digits of a house sum to a known value, so the cells of the house outside
+/// the cages within it (the innies) sum to the rest. If the cages overlapping the house cover
+/// it, their cells outside of it (the outies) sum to the excess of the cages over the house.
+pub(super) fn innies_outies(grid: &Grid) -> Option<Step> {
+    let cages = grid
+        .constraints
+        .iter()
+        .filter_map(|constraint| match &constraint.kind {
+            ConstraintKind::Sum(cells, sum) => Some((constraint, cells, *sum)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for house in &grid.houses {
+        let Some(house_digits) = house.digits else {
+            continue;
+        };
+        let total = bits(house_digits).map(|i| grid.digits[i]).sum::<i32>();
+
+        let inside = house_cages(&cages, |cells| {
+            cells.iter().all(|cell| house.cells.contains(cell))
+        });
+        let overlapping = house_cages(&cages, |cells| {
+            cells.iter().any(|cell| house.cells.contains(cell))
+        });
+
+        let innies = house
+            .cells
+            .iter()
+            .copied()
+            .filter(|cell| !inside.iter().any(|(_, cells, _)| cells.contains(cell)))
+            .collect::<Vec<_>>();
+        if !inside.is_empty()
+            && (1..=MAX_INNIES_OUTIES).contains(&innies.len())
+            && !is_cage(&cages, &innies)
+        {
+            let terms = innies.iter().map(|&cell| (cell, 1)).collect::<Vec<_>>();
+            let sum = total - inside.iter().map(|(_, _, sum)| sum).sum::<i32>();
+            let constraints = inside
+                .iter()
+                .map(|(constraint, _, _)| *constraint)
+                .collect();
+            if let Some(mut step) =
+                sum_step(grid, &terms, sum, Technique::InniesOuties, constraints)
+            {
+                step.houses.insert(0, house.instance.clone());
+                return Some(step);
+            }
+        }
+
+        let covered = house
+            .cells
+            .iter()
+            .all(|cell| overlapping.iter().any(|(_, cells, _)| cells.contains(cell)));
+        if covered && overlapping.len() > inside.len() {
+            let outies = overlapping
+                .iter()
+                .flat_map(|(_, cells, _)| cells.iter().copied())
+                .filter(|cell| !house.cells.contains(cell))
+                .collect::<Vec<_>>();
+            if outies.len() > MAX_INNIES_OUTIES || is_cage(&cages, &outies) {
+                continue;
+            }
+            let terms = outies.iter().map(|&cell| (cell, 1)).collect::<Vec<_>>();
+            let sum = overlapping.iter().map(|(_, _, sum)| sum).sum::<i32>() - total;
+            let constraints = overlapping
+                .iter()
+                .map(|(constraint, _, _)| *constraint)
+                .collect();
+            if let Some(mut step) =
+                sum_step(grid, &terms, sum, Technique::InniesOuties, constraints)
+            {
+                step.houses.insert(0, house.instance.clone());
+                return Some(step);
+            }
+        }
+    }
+    None
+}
+
+type Cage<'a> = (&'a Constraint, &'a Vec<(usize, usize)>, i32);
+
+/// Returns the cages satisfying `filter`, or nothing if some of them overlap, as the sums would
+/// not add up then.
+fn house_cages<'a>(
+    cages: &[Cage<'a>],
+    filter: impl Fn(&[(usize, usize)]) -> bool,
+) -> Vec<Cage<'a>> {
+    let selected = cages
+        .iter()
+        .filter(|(_, cells, _)| filter(cells))
+        .copied()
+        .collect::<Vec<_>>();
+    let mut cells = selected
+        .iter()
+        .flat_map(|(_, cells, _)| cells.iter().copied())
+        .collect::<Vec<_>>();
+    let len = cells.len();
+    cells.sort();
+    cells.dedup();
+    if cells.len() != len {
+        return vec![];
+    }
+    selected
+}
+
+/// Whether `cells` are exactly the cells of one of `cages`, which other techniques handle.
+fn is_cage(cages: &[Cage], cells: &[(usize, usize)]) -> bool {
+    cages.iter().any(|(_, cage, _)| {
+        cage.len() == cells.len() && cells.iter().all(|cell| cage.contains(cell))
+    })
+}
+
+/// Eliminates the candidates which appear in no assignment of digits to the cells of `terms`
+/// with `sum(coefficient * digit) == sum`. Returns a step based on `constraints` if there is
+/// any.
+fn sum_step(
+    grid: &Grid,
+    terms: &[((usize, usize), i32)],
+    sum: i32,
+    technique: Technique,
+    constraints: Vec<&Constraint>,
+) -> Option<Step> {
+    let supported = supported(grid, terms, sum);
+    let mut eliminations = vec![];
+    for (&((y, x), _), support) in terms.iter().zip(supported) {
+        for i in bits(grid.candidates[y][x] & !support) {
+            let candidate = grid.candidate(y, x, i);
+            if !eliminations.contains(&candidate) {
+                eliminations.push(candidate);
+            }
+        }
+    }
+    if eliminations.is_empty() {
+        return None;
+    }
+
+    Some(Step {
+        technique,
+        houses: constraints
+            .iter()
+            .map(|constraint| constraint.instance.clone())
+            .collect(),
+        candidates: vec![],
+        links: vec![],
+        placements: vec![],
+        eliminations,
+    })
+}
+
+/// Returns, for each term, the digit indices it takes in some assignment satisfying the sum, in
+/// which cells sharing a house have different digits.
+///
+/// Each digit not yet known to be supported is tried in turn, looking for a single assignment
+/// containing it, and all digits of a found assignment are marked as supported.
+fn supported(grid: &Grid, terms: &[((usize, usize), i32)], sum: i32) -> Vec<Mask> {
+    let options = terms
+        .iter()
+        .map(|&((y, x), _)| grid.options(y, x))
+        .collect::<Vec<_>>();
+    let mut supported = vec![0; terms.len()];
+    for k in 0..terms.len() {
+        for i in bits(options[k]) {
+            if (supported[k] >> i) & 1 != 0 {
+                continue;
+            }
+            let mut fixed = options.clone();
+            fixed[k] = 1 << i;
+            let mut assignment = vec![];
+            if find_assignment(grid, terms, &fixed, sum, &mut assignment) {
+                for (k, &j) in assignment.iter().enumerate() {
+                    supported[k] |= 1 << j;
+                }
+            }
+        }
+    }
+    supported
+}
+
+fn find_assignment(
+    grid: &Grid,
+    terms: &[((usize, usize), i32)],
+    options: &[Mask],
+    rest: i32,
+    assignment: &mut Vec<usize>,
+) -> bool {
+    let k = assignment.len();
+    if k == terms.len() {
+        return rest == 0;
+    }
+
+    // Bounds of the remaining terms, ignoring that some of their digits must differ.
+    let (mut low, mut high) = (0, 0);
+    for (&(_, coefficient), &mask) in terms[k..].iter().zip(&options[k..]) {
+        let values = bits(mask)
+            .map(|i| coefficient * grid.digits[i])
+            .collect::<Vec<_>>();
+        low += values.iter().min().unwrap_or(&0);
+        high += values.iter().max().unwrap_or(&0);
+    }
+    if !(low..=high).contains(&rest) {
+        return false;
+    }
+
+    let (cell, coefficient) = terms[k];
+    for i in bits(options[k]) {
+        let conflicts = assignment
+            .iter()
+            .enumerate()
+            .any(|(l, &j)| j == i && grid.sees(terms[l].0, cell));
+        if conflicts {
+            continue;
+        }
+        assignment.push(i);
+        if find_assignment(
+            grid,
+            terms,
+            options,
+            rest - coefficient * grid.digits[i],
+            assignment,
+        ) {
+            return true;
+        }
+        assignment.pop();
+    }
+    false
+}
@@ -0,0 +1,71 @@
+use crate::error::SolverError;
+use crate::logic::{bits, unsupported_rules, Grid};
+use crate::puzzle::Puzzle;
+
+/// Returns the minimum depth of lookahead over the propagation of the logic module at which
+/// `puzzle` is solved, or `None` if it has no unique solution or needs a depth above
+/// `max_depth`.
+///
+/// At depth 0, the grid is only propagated: the techniques of `Grid::propagate`, which look at
+/// a single house or constraint (singles, cage combinations, thermo ranges, ...), are applied
+/// until they get stuck. At depth `d`, a candidate is eliminated if placing it leads to a
+/// contradiction at depth `d - 1`, and the grid is propagated again after each round of such
+/// tests. The depth is thus measured over what these techniques understand rather than over the
+/// SAT encoding, and does not depend on the harder techniques `rating::rate` uses.
+///
+/// Puzzles with rules the logic module does not model (see `logic::unsupported_rules`) are
+/// rejected with an `UnsupportedValue` error, as they would seem to have no unique solution.
+pub fn propagation_lookahead_depth(
+    puzzle: &Puzzle,
+    max_depth: usize,
+) -> Result<Option<usize>, SolverError> {
+    if let Some(rule) = unsupported_rules(puzzle).first() {
+        return Err(SolverError::unsupported_value(
+            rule,
+            "the lookahead does not support this rule",
+        ));
+    }
+    let mut grid = Grid::new(puzzle)?;
+    for depth in 0..=max_depth {
+        if !lookahead(&mut grid, depth) {
+            return Ok(None);
+        }
+        if grid.is_solved() {
+            return Ok(Some(depth));
+        }
+    }
+    Ok(None)
+}
+
+/// Solves `grid` with lookahead of `depth` as far as possible. Returns `false` if it runs into a
+/// contradiction.
+fn lookahead(grid: &mut Grid, depth: usize) -> bool {
+    loop {
+        grid.propagate();
+        if grid.is_contradictory() {
+            return false;
+        }
+        if depth == 0 || grid.is_solved() {
+            return true;
+        }
+
+        let mut progress = false;
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                for i in bits(grid.candidates[y][x]) {
+                    let snapshot = grid.snapshot();
+                    grid.place(y, x, i);
+                    let consistent = lookahead(grid, depth - 1);
+                    grid.restore(snapshot);
+                    if !consistent {
+                        grid.eliminate(y, x, i);
+                        progress = true;
+                    }
+                }
+            }
+        }
+        if !progress {
+            return true;
+        }
+    }
+}
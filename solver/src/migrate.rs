@@ -0,0 +1,93 @@
+use serde_json::{Map, Value};
+
+use crate::error::SolverError;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), SolverError>;
+
+/// `MIGRATIONS[v]` upgrades a document of version `v` to version `v + 1`.
+const MIGRATIONS: &[Migration] = &[from_v0, from_v1];
+
+/// The version of the puzzle format described by `Puzzle`.
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Upgrades a puzzle JSON of any older version to `CURRENT_VERSION` in place.
+///
+/// Documents without a `version` field are of version 0. Documents of a newer version than
+/// this solver knows are rejected rather than guessed at.
+pub fn migrate(puzzle: &mut Value) -> Result<(), SolverError> {
+    let puzzle = puzzle
+        .as_object_mut()
+        .ok_or_else(|| SolverError::parse_error("the puzzle must be a JSON object"))?;
+
+    let version = match puzzle.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .filter(|&version| version <= CURRENT_VERSION as u64)
+            .ok_or_else(|| {
+                SolverError::parse_error(format!(
+                    "unsupported puzzle version {} (the latest supported version is {})",
+                    version, CURRENT_VERSION
+                ))
+            })? as u32,
+    };
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(puzzle)?;
+    }
+    puzzle.insert("version".to_string(), Value::from(CURRENT_VERSION));
+    Ok(())
+}
+
+/// Version 1 only introduced the `version` field itself.
+fn from_v0(_puzzle: &mut Map<String, Value>) -> Result<(), SolverError> {
+    Ok(())
+}
+
+/// Version 2 indexes `forbiddenCandidates.isForbidden` by digit value instead of by position
+/// in the digit set.
+fn from_v1(puzzle: &mut Map<String, Value>) -> Result<(), SolverError> {
+    let digits = digits_of(puzzle);
+    let Some(is_forbidden) = puzzle
+        .get_mut("forbiddenCandidates")
+        .and_then(|rule| rule.get_mut("isForbidden"))
+        .and_then(Value::as_array_mut)
+    else {
+        return Ok(());
+    };
+    let len = digits.last().map_or(0, |&d| d as usize + 1);
+
+    // Malformed data is left to validation.
+    for row in is_forbidden.iter_mut().filter_map(Value::as_array_mut) {
+        for cell in row.iter_mut() {
+            let Some(by_position) = cell.as_array() else {
+                continue;
+            };
+            let mut by_value = vec![false; len];
+            for (&d, forbidden) in digits.iter().zip(by_position) {
+                by_value[d as usize] = forbidden.as_bool() == Some(true);
+            }
+            *cell = Value::from(by_value);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the non-negative digits of a version 1 document in ascending order, as
+/// `Puzzle::digits` would.
+fn digits_of(puzzle: &Map<String, Value>) -> Vec<u64> {
+    if let Some(digits) = puzzle.get("digits").and_then(Value::as_array) {
+        let mut digits = digits.iter().filter_map(Value::as_u64).collect::<Vec<_>>();
+        digits.sort();
+        digits.dedup();
+        return digits;
+    }
+    let dimension = |key: &str| {
+        puzzle
+            .get(key)
+            .or_else(|| puzzle.get("size"))
+            .and_then(Value::as_u64)
+            .unwrap_or(0)
+    };
+    (1..=dimension("height").max(dimension("width"))).collect()
+}
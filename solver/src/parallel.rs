@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use cspuz_rs::solver::any;
+
+use crate::error::SolverError;
+use crate::puzzle::Puzzle;
+use crate::solver::{
+    build_model, facts_from_search, unsatisfiable, Budget, SolveResult, SolverConfig,
+};
+
+/// Computes the facts shared by all solutions of `puzzle` on `num_threads` threads.
+///
+/// The rows of the board are distributed among the threads, each of which searches the
+/// candidates of its current row with its own solver as in `search_candidates`. Solutions
+/// found by any thread are shared, so that the other threads need not search for the
+/// candidates they place.
+pub fn irrefutable_facts_parallel(
+    puzzle: &Puzzle,
+    config: SolverConfig,
+    num_threads: usize,
+) -> Result<SolveResult, SolverError> {
+    let budget = Budget::new(&config);
+    let (h, w) = (puzzle.height(), puzzle.width());
+    let digits = puzzle.digits();
+
+    // possible[y][x][i]: some solution found so far has digits[i] at (y, x)
+    let possible = Mutex::new(vec![vec![vec![false; digits.len()]; w]; h]);
+    let mark_possible = |answer: &[Vec<i32>]| {
+        let mut possible = possible.lock().unwrap();
+        for (y, row) in answer.iter().enumerate() {
+            for (x, &n) in row.iter().enumerate() {
+                if let Ok(i) = digits.binary_search(&n) {
+                    possible[y][x][i] = true;
+                }
+            }
+        }
+    };
+
+    // Find a first solution before starting the threads, so that unsatisfiable puzzles are
+    // handled as usual.
+    {
+        let (mut solver, nums, instances) = build_model(puzzle, &config, None)?;
+        let Some(model) = solver.solve() else {
+            return unsatisfiable(puzzle, &config, &budget, &instances, None);
+        };
+        mark_possible(&model.get(&nums));
+    }
+
+    let finished = Mutex::new(vec![vec![false; w]; h]);
+    let next_row = AtomicUsize::new(0);
+
+    let search_rows = || -> Result<(), SolverError> {
+        loop {
+            let y = next_row.fetch_add(1, Ordering::Relaxed);
+            if y >= h {
+                return Ok(());
+            }
+
+            let (mut solver, nums, _) = build_model(puzzle, &config, None)?;
+            loop {
+                let mut unseen = vec![];
+                {
+                    let possible = possible.lock().unwrap();
+                    for x in 0..w {
+                        for (i, &d) in digits.iter().enumerate() {
+                            if !possible[y][x][i] {
+                                unseen.push(nums.at((y, x)).eq(d));
+                            }
+                        }
+                    }
+                }
+                if unseen.is_empty() {
+                    break;
+                }
+                if budget.is_exhausted() {
+                    return Ok(());
+                }
+                solver.add_expr(any(unseen));
+
+                let Some(model) = solver.solve() else {
+                    break;
+                };
+                mark_possible(&model.get(&nums));
+            }
+
+            finished.lock().unwrap()[y] = vec![true; w];
+        }
+    };
+
+    thread::scope(|scope| {
+        let workers = (0..num_threads.max(1))
+            .map(|_| scope.spawn(search_rows))
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().unwrap())
+    })?;
+
+    facts_from_search(
+        puzzle,
+        &config,
+        &budget,
+        None,
+        &possible.into_inner().unwrap(),
+        &finished.into_inner().unwrap(),
+    )
+}
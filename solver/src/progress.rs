@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ErrorCode, SolverError};
+use crate::puzzle::{Candidate, Pos, Puzzle};
+use crate::solver::{check_grid, irrefutable_facts, IrrefutableFacts, SolveResult, SolverConfig};
+
+/// The digits and pencil marks entered by a player, in the format of the editor's `answer` rule.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PlayerState {
+    /// The entered digits. May be empty if nothing has been entered.
+    #[serde(default)]
+    pub numbers: Vec<Vec<Option<i32>>>,
+
+    /// For each cell, the digits the player still considers possible, or `null` if the cell
+    /// has no pencil marks. May be empty if no pencil marks have been entered.
+    #[serde(rename = "pencilMarks", default)]
+    pub pencil_marks: Vec<Vec<Option<Vec<i32>>>>,
+}
+
+impl PlayerState {
+    /// Reads the player's state from the `answer` key of the puzzle JSON, as sent by the editor.
+    pub fn from_puzzle(puzzle: &Puzzle) -> Result<PlayerState, SolverError> {
+        match puzzle.extra.get("answer") {
+            Some(answer) => serde_json::from_value(answer.clone()).map_err(|err| {
+                SolverError::new(ErrorCode::ParseError, Some("answer"), err.to_string())
+            }),
+            None => Ok(PlayerState::default()),
+        }
+    }
+
+    /// Returns the digit entered at (y, x), if any.
+    pub fn number(&self, y: usize, x: usize) -> Option<i32> {
+        self.numbers
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .flatten()
+    }
+
+    /// Returns the pencil marks of (y, x), or `None` if the cell has none.
+    pub fn pencil_marks(&self, y: usize, x: usize) -> Option<&[i32]> {
+        self.pencil_marks
+            .get(y)
+            .and_then(|row| row.get(x))
+            .and_then(|marks| marks.as_deref())
+    }
+
+    /// Checks that the entered grids match the board of `puzzle`.
+    pub(crate) fn check_shape(&self, puzzle: &Puzzle) -> Result<(), SolverError> {
+        let (h, w) = (puzzle.height(), puzzle.width());
+        if !self.numbers.is_empty() {
+            check_grid("answer", "numbers", &self.numbers, h, w)?;
+        }
+        if !self.pencil_marks.is_empty() {
+            check_grid("answer", "pencilMarks", &self.pencil_marks, h, w)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of `puzzle` with the entered digits added as given numbers. Givens take
+    /// precedence over entries in the same cell.
+    pub fn apply(&self, puzzle: &Puzzle) -> Puzzle {
+        let (h, w) = (puzzle.height(), puzzle.width());
+        let given = |y: usize, x: usize| {
+            let numbers = &puzzle.given_numbers.numbers;
+            numbers.get(y).and_then(|row| row.get(x)).copied().flatten()
+        };
+
+        let mut applied = puzzle.clone();
+        applied.given_numbers.numbers = (0..h)
+            .map(|y| (0..w).map(|x| given(y, x).or(self.number(y, x))).collect())
+            .collect();
+        applied
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProgressReport {
+    /// Entered digits which no solution of the puzzle has at their cell.
+    #[serde(rename = "wrongEntries")]
+    pub wrong_entries: Vec<Candidate>,
+
+    /// Digits missing from the pencil marks of a cell although they are still possible there.
+    #[serde(rename = "wrongEliminations")]
+    pub wrong_eliminations: Vec<Candidate>,
+
+    /// Whether the puzzle still has a solution agreeing with all entered digits.
+    pub solvable: bool,
+
+    /// Whether the time limit of the config was reached. If so, some wrong entries may be
+    /// missing and some pencil marks may be wrongly reported.
+    #[serde(rename = "timedOut")]
+    pub timed_out: bool,
+}
+
+/// Checks the player's entries against the solutions of `puzzle`.
+///
+/// The backend has no assumption interface, so the entries are added as given numbers to a
+/// copy of the puzzle, which is solved instead of the puzzle itself. Only if that copy has no
+/// solution is the puzzle solved as well, to tell which entries are wrong by themselves. Pencil
+/// marks are checked against the solutions agreeing with the entries, or against all solutions
+/// of the puzzle if there is none.
+pub fn check_progress(
+    puzzle: &Puzzle,
+    state: &PlayerState,
+    config: SolverConfig,
+) -> Result<ProgressReport, SolverError> {
+    state.check_shape(puzzle)?;
+
+    let (facts, state_solvable) = match irrefutable_facts(&state.apply(puzzle), config.clone())? {
+        SolveResult::Satisfiable(facts) => (facts, true),
+        SolveResult::Unsatisfiable { .. } => match irrefutable_facts(puzzle, config)? {
+            SolveResult::Satisfiable(facts) => (facts, false),
+            SolveResult::Unsatisfiable { .. } => {
+                return Ok(ProgressReport {
+                    wrong_entries: vec![],
+                    wrong_eliminations: vec![],
+                    solvable: false,
+                    timed_out: false,
+                });
+            }
+        },
+    };
+
+    // Entries in empty cells are part of the solutions of `facts` if the state is solvable, so
+    // only entries overwritten by a given can be reported then.
+    let mut wrong_entries = vec![];
+    for y in 0..puzzle.height() {
+        for x in 0..puzzle.width() {
+            if let Some(digit) = state.number(y, x) {
+                if !is_possible(&facts, y, x, digit) {
+                    wrong_entries.push(Candidate {
+                        cell: Pos { x, y },
+                        digit,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut wrong_eliminations = vec![];
+    for y in 0..puzzle.height() {
+        for x in 0..puzzle.width() {
+            let Some(marks) = state.pencil_marks(y, x) else {
+                continue;
+            };
+            if state.number(y, x).is_some() {
+                continue;
+            }
+            for &digit in &facts.digits {
+                if !marks.contains(&digit) && is_possible(&facts, y, x, digit) {
+                    wrong_eliminations.push(Candidate {
+                        cell: Pos { x, y },
+                        digit,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(ProgressReport {
+        solvable: state_solvable && wrong_entries.is_empty(),
+        wrong_entries,
+        wrong_eliminations,
+        timed_out: facts.timed_out,
+    })
+}
+
+pub(crate) fn is_possible(facts: &IrrefutableFacts, y: usize, x: usize, digit: i32) -> bool {
+    digit >= 0 && facts.candidates[y][x].get(digit as usize) == Some(&true)
+}
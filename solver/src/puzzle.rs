@@ -1,67 +1,203 @@
-use serde::Deserialize;
-
-#[derive(Debug, Clone, Deserialize, Default)]
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::migrate::{self, CURRENT_VERSION};
+use crate::rule::Rule;
+
+/// A puzzle in the current format.
+///
+/// Deserializing a `Puzzle` always upgrades the document with `migrate::migrate` first, so
+/// that older documents cannot be read as if they were of the current version.
+// `remote = "Self"` turns the derived impls into inherent functions, which the `Serialize` and
+// `Deserialize` impls below wrap.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(remote = "Self")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Puzzle {
+    /// The version of the puzzle format. Documents without it are of version 0; older
+    /// documents are upgraded by `migrate::migrate`.
+    #[serde(default)]
+    pub version: u32,
+
+    /// The side length of a square board. Ignored for the dimensions given by `height` and
+    /// `width`.
+    #[serde(default)]
     pub size: usize,
 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<usize>,
+
+    /// The digits which can be placed in cells. Defaults to `1..=max(height, width)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digits: Option<Vec<i32>>,
+
+    /// The multiset of digits each house (row, column, block, diagonal) must contain, e.g.
+    /// `[0, 1, ..., 9]` for 10-cell houses with a blank written as 0. Defaults to `digits`.
+    #[serde(
+        rename = "houseDigits",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub house_digits: Option<Vec<i32>>,
+
     #[serde(rename = "givenNumbers")]
     pub given_numbers: GivenNumbers,
 
-    #[serde(rename = "blocks")]
+    #[serde(rename = "blocks", skip_serializing_if = "Option::is_none")]
     pub blocks: Option<Blocks>,
 
-    #[serde(rename = "oddEven")]
+    #[serde(rename = "oddEven", skip_serializing_if = "Option::is_none")]
     pub odd_even: Option<OddEven>,
 
-    #[serde(rename = "nonConsecutive")]
+    #[serde(rename = "nonConsecutive", skip_serializing_if = "Option::is_none")]
     pub non_consecutive: Option<NonConsecutive>,
 
-    #[serde(rename = "xv")]
+    #[serde(rename = "xv", skip_serializing_if = "Option::is_none")]
     pub xv: Option<XV>,
 
-    #[serde(rename = "diagonal")]
+    #[serde(rename = "diagonal", skip_serializing_if = "Option::is_none")]
     pub diagonal: Option<Diagonal>,
 
-    #[serde(rename = "arrow")]
+    #[serde(rename = "arrow", skip_serializing_if = "Option::is_none")]
     pub arrow: Option<Arrow>,
 
-    #[serde(rename = "thermo")]
+    #[serde(rename = "thermo", skip_serializing_if = "Option::is_none")]
     pub thermo: Option<Thermo>,
 
-    #[serde(rename = "killer")]
+    #[serde(rename = "killer", skip_serializing_if = "Option::is_none")]
     pub killer: Option<Killer>,
 
-    #[serde(rename = "consecutive")]
+    #[serde(rename = "consecutive", skip_serializing_if = "Option::is_none")]
     pub consecutive: Option<Consecutive>,
 
-    #[serde(rename = "skyscrapers")]
+    #[serde(rename = "skyscrapers", skip_serializing_if = "Option::is_none")]
     pub skyscrapers: Option<Skyscrapers>,
 
-    #[serde(rename = "xSums")]
+    #[serde(rename = "xSums", skip_serializing_if = "Option::is_none")]
     pub x_sums: Option<XSums>,
 
-    #[serde(rename = "extraRegions")]
+    #[serde(rename = "extraRegions", skip_serializing_if = "Option::is_none")]
     pub extra_regions: Option<ExtraRegions>,
 
-    #[serde(rename = "palindrome")]
+    #[serde(rename = "palindrome", skip_serializing_if = "Option::is_none")]
     pub palindrome: Option<Palindrome>,
 
-    #[serde(rename = "forbiddenCandidates")]
+    #[serde(
+        rename = "forbiddenCandidates",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub forbidden_candidates: Option<ForbiddenCandidates>,
 
-    #[serde(rename = "antiKnight")]
+    #[serde(rename = "antiKnight", skip_serializing_if = "Option::is_none")]
     pub anti_knight: Option<AntiKnight>,
 
-    #[serde(rename = "noTouch")]
+    #[serde(rename = "noTouch", skip_serializing_if = "Option::is_none")]
     pub no_touch: Option<NoTouch>,
+
+    /// Rules without a dedicated field, parsed by `RuleRegistry::parse_puzzle`. They are not
+    /// serialized themselves: their data is kept in `extra`.
+    #[serde(skip)]
+    pub custom_rules: Vec<Arc<dyn Rule>>,
+
+    /// The data of all keys which are not built-in rules.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl<'de> Deserialize<'de> for Puzzle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut puzzle_json = serde_json::Value::deserialize(deserializer)?;
+        migrate::migrate(&mut puzzle_json).map_err(serde::de::Error::custom)?;
+        Puzzle::deserialize(puzzle_json).map_err(serde::de::Error::custom)
+    }
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+impl Serialize for Puzzle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Puzzle::serialize(self, serializer)
+    }
+}
+
+impl Default for Puzzle {
+    fn default() -> Self {
+        Puzzle {
+            version: CURRENT_VERSION,
+            size: 0,
+            height: None,
+            width: None,
+            digits: None,
+            house_digits: None,
+            given_numbers: GivenNumbers::default(),
+            blocks: None,
+            odd_even: None,
+            non_consecutive: None,
+            xv: None,
+            diagonal: None,
+            arrow: None,
+            thermo: None,
+            killer: None,
+            consecutive: None,
+            skyscrapers: None,
+            x_sums: None,
+            extra_regions: None,
+            palindrome: None,
+            forbidden_candidates: None,
+            anti_knight: None,
+            no_touch: None,
+            custom_rules: vec![],
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+impl Puzzle {
+    pub fn height(&self) -> usize {
+        self.height.unwrap_or(self.size)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width.unwrap_or(self.size)
+    }
+
+    /// Returns the digit set of the puzzle in ascending order, without duplicates.
+    pub fn digits(&self) -> Vec<i32> {
+        match &self.digits {
+            Some(digits) => {
+                let mut digits = digits.clone();
+                digits.sort();
+                digits.dedup();
+                digits
+            }
+            None => (1..=self.height().max(self.width()) as i32).collect(),
+        }
+    }
+
+    /// Returns the multiset of digits each house must contain, in ascending order.
+    pub fn house_digits(&self) -> Vec<i32> {
+        match &self.house_digits {
+            Some(house_digits) => {
+                let mut house_digits = house_digits.clone();
+                house_digits.sort();
+                house_digits
+            }
+            None => self.digits(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GivenNumbers {
     pub numbers: Vec<Vec<Option<i32>>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Blocks {
     #[serde(rename = "horizontalBorder")]
     pub horizontal: Vec<Vec<bool>>,
@@ -70,16 +206,19 @@ pub struct Blocks {
     pub vertical: Vec<Vec<bool>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct OddEven {
     #[serde(rename = "cellKind")]
     pub cell_kind: Vec<Vec<i32>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct NonConsecutive {}
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct XV {
     #[serde(rename = "horizontalBorder")]
     pub horizontal: Vec<Vec<i32>>,
@@ -91,7 +230,8 @@ pub struct XV {
     pub all_shown: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Diagonal {
     #[serde(rename = "mainDiagonal")]
     pub main_diagonal: bool,
@@ -100,23 +240,34 @@ pub struct Diagonal {
     pub anti_diagonal: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Pos {
     pub x: usize,
     pub y: usize,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A digit at a cell, e.g. an entry or a pencil mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Candidate {
+    pub cell: Pos,
+    pub digit: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Arrow {
     pub arrows: Vec<Vec<Pos>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Thermo {
     pub thermos: Vec<Vec<Pos>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct KillerRegion {
     pub cells: Vec<Pos>,
 
@@ -124,13 +275,15 @@ pub struct KillerRegion {
     pub sum: Option<i32>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Killer {
     pub regions: Vec<KillerRegion>,
     pub distinct: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Consecutive {
     #[serde(rename = "horizontalBorder")]
     pub horizontal: Vec<Vec<bool>>,
@@ -142,7 +295,8 @@ pub struct Consecutive {
     pub all_shown: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Skyscrapers {
     pub up: Vec<Option<i32>>,
     pub down: Vec<Option<i32>>,
@@ -150,7 +304,8 @@ pub struct Skyscrapers {
     pub right: Vec<Option<i32>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct XSums {
     pub up: Vec<Option<i32>>,
     pub down: Vec<Option<i32>>,
@@ -158,31 +313,44 @@ pub struct XSums {
     pub right: Vec<Option<i32>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtraRegion {
     pub cells: Vec<Pos>,
+
+    /// The multiset of digits the region must contain, if different from the house digits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digits: Option<Vec<i32>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtraRegions {
     pub regions: Vec<ExtraRegion>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Palindrome {
     pub palindromes: Vec<Vec<Pos>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ForbiddenCandidates {
+    /// `isForbidden[y][x][v]` tells whether the digit `v` is ruled out at (y, x), for `v` up
+    /// to the largest digit. Indexed by value like `IrrefutableFacts::candidates`; before
+    /// version 2, the index was the position of the digit in the digit set.
     #[serde(rename = "isForbidden")]
     pub is_forbidden: Vec<Vec<Vec<bool>>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AntiKnight {}
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct NoTouch {}
 
 pub const ODDEVEN_NO_CONSTRAINT: i32 = 0;
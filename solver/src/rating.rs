@@ -0,0 +1,184 @@
+use serde::Serialize;
+
+use crate::error::SolverError;
+use crate::logic::{unsupported_rules, Grid, Technique};
+use crate::progress::is_possible;
+use crate::puzzle::Puzzle;
+use crate::solver::{irrefutable_facts, IrrefutableFacts, SolveResult, SolverConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Tier {
+    Easy,
+    Medium,
+    Hard,
+    Extreme,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rating {
+    /// The difficulty on a scale from 0 (nothing to deduce) to 10.
+    pub score: f64,
+
+    pub tier: Tier,
+
+    /// The hardest technique used, if any.
+    #[serde(rename = "hardestTechnique")]
+    pub hardest_technique: Option<Technique>,
+
+    /// The number of candidates which had to be eliminated by trial and error, i.e. by placing
+    /// them and running into a contradiction with the techniques.
+    pub trials: usize,
+
+    /// Whether some candidates could not be eliminated even by trial and error of a single
+    /// candidate.
+    #[serde(rename = "nestedTrials")]
+    pub nested_trials: bool,
+}
+
+/// The score of each technique. Scores increase in the order of `Technique`.
+fn technique_score(technique: Technique) -> f64 {
+    match technique {
+        Technique::HiddenSingle => 1.0,
+        Technique::NakedSingle => 1.5,
+        Technique::PairRestriction => 1.6,
+        Technique::PalindromeEquality => 1.7,
+        Technique::ThermoRange => 1.8,
+        Technique::CageCombination => 1.9,
+        Technique::LockedCandidates => 2.0,
+        Technique::ArrowSum => 2.2,
+        Technique::InniesOuties => 2.4,
+        Technique::NakedPair => 2.5,
+        Technique::HiddenPair => 2.8,
+        Technique::NakedTriple => 3.0,
+        Technique::HiddenTriple => 3.2,
+        Technique::XWing => 3.4,
+        Technique::NakedQuad => 3.8,
+        Technique::HiddenQuad => 4.0,
+        Technique::Swordfish => 4.2,
+        Technique::Jellyfish => 4.6,
+        Technique::XYChain => 5.0,
+        Technique::AlternatingInferenceChain => 5.6,
+    }
+}
+
+/// The score of a puzzle needing one round of trial and error. Each further round adds
+/// `TRIAL_ROUND_SCORE`, up to `MAX_TRIAL_SCORE`.
+const TRIAL_SCORE: f64 = 6.0;
+const TRIAL_ROUND_SCORE: f64 = 0.5;
+const MAX_TRIAL_SCORE: f64 = 9.5;
+
+/// The score of a puzzle needing nested trial and error.
+const NESTED_TRIAL_SCORE: f64 = 10.0;
+
+fn tier_of(score: f64) -> Tier {
+    if score < 2.0 {
+        Tier::Easy
+    } else if score < technique_score(Technique::XWing) {
+        Tier::Medium
+    } else if score < TRIAL_SCORE {
+        Tier::Hard
+    } else {
+        Tier::Extreme
+    }
+}
+
+/// Rates the difficulty of `puzzle` for a human solver. Returns `None` if it has no solution.
+///
+/// The puzzle is solved with the techniques of `logic`, always using the easiest one which
+/// makes progress, and scored by the hardest one needed. The target is the backbone computed by
+/// `irrefutable_facts`, so puzzles with several solutions are rated by how hard their
+/// determined part is. Where the techniques get stuck, the candidates the backbone rules out
+/// are tested one by one: a candidate whose placement leads the techniques into a
+/// contradiction is eliminated. Each round of such trials raises the score.
+///
+/// The techniques ignore some rules (see `logic::unsupported_rules`), which would turn their
+/// eliminations into trials and inflate the score, so puzzles with such rules are rejected with
+/// an `UnsupportedValue` error.
+pub fn rate(puzzle: &Puzzle, config: SolverConfig) -> Result<Option<Rating>, SolverError> {
+    if let Some(rule) = unsupported_rules(puzzle).first() {
+        return Err(SolverError::unsupported_value(
+            rule,
+            "the rating does not support this rule",
+        ));
+    }
+    let SolveResult::Satisfiable(facts) = irrefutable_facts(puzzle, config)? else {
+        return Ok(None);
+    };
+    rate_against(puzzle, &facts).map(Some)
+}
+
+/// Rates `puzzle`, whose backbone is given by `facts`.
+fn rate_against(puzzle: &Puzzle, facts: &IrrefutableFacts) -> Result<Rating, SolverError> {
+    let mut grid = Grid::new(puzzle)?;
+    let mut hardest = hardest_of(&mut grid);
+    let mut trials = 0;
+    let mut rounds = 0;
+    let mut nested_trials = false;
+
+    loop {
+        let unresolved = unresolved_candidates(&grid, facts);
+        if unresolved.is_empty() {
+            break;
+        }
+
+        let mut eliminated = vec![];
+        for (y, x, i) in unresolved {
+            let snapshot = grid.snapshot();
+            grid.place(y, x, i);
+            grid.solve();
+            if grid.is_contradictory() {
+                eliminated.push((y, x, i));
+            }
+            grid.restore(snapshot);
+        }
+        if eliminated.is_empty() {
+            nested_trials = true;
+            break;
+        }
+
+        rounds += 1;
+        trials += eliminated.len();
+        for (y, x, i) in eliminated {
+            grid.eliminate(y, x, i);
+        }
+        hardest = hardest.max(hardest_of(&mut grid));
+    }
+
+    let technique = hardest.map_or(0.0, technique_score);
+    let score = if nested_trials {
+        NESTED_TRIAL_SCORE
+    } else if rounds > 0 {
+        (TRIAL_SCORE + TRIAL_ROUND_SCORE * (rounds - 1) as f64).min(MAX_TRIAL_SCORE)
+    } else {
+        technique
+    };
+
+    Ok(Rating {
+        score,
+        tier: tier_of(score),
+        hardest_technique: hardest,
+        trials,
+        nested_trials,
+    })
+}
+
+/// Solves `grid` as far as the techniques go and returns the hardest one used.
+fn hardest_of(grid: &mut Grid) -> Option<Technique> {
+    grid.solve().iter().map(|step| step.technique).max()
+}
+
+/// Returns the candidates of `grid` which no solution has, as (y, x, digit index).
+fn unresolved_candidates(grid: &Grid, facts: &IrrefutableFacts) -> Vec<(usize, usize, usize)> {
+    let mut res = vec![];
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            for (i, &digit) in grid.digits.iter().enumerate() {
+                if grid.has_candidate(y, x, i) && !is_possible(facts, y, x, digit) {
+                    res.push((y, x, i));
+                }
+            }
+        }
+    }
+    res
+}
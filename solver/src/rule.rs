@@ -0,0 +1,407 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{ErrorCode, SolverError};
+use crate::migrate;
+use crate::puzzle::{
+    AntiKnight, Arrow, Blocks, Consecutive, Diagonal, ExtraRegions, ForbiddenCandidates,
+    GivenNumbers, Killer, NoTouch, NonConsecutive, OddEven, Palindrome, Puzzle, Skyscrapers,
+    Thermo, XSums, XV,
+};
+use crate::solver::{self, Encoder};
+use crate::validate::{self, Validator};
+
+/// A sudoku variant rule.
+///
+/// The built-in rules are stored in the dedicated fields of `Puzzle`. Other rules can be
+/// registered to a `RuleRegistry`, which parses them from the puzzle JSON into
+/// `Puzzle::custom_rules`. `solver::{cells_of, check_cell, check_grid, border_index}` help
+/// implementing `encode` like the built-in rules do.
+pub trait Rule: std::fmt::Debug + Send + Sync {
+    /// The key of the rule in the puzzle JSON, which is also the rule name used by the editor.
+    fn key(&self) -> &str;
+
+    /// Reports problems in the rule data without invoking the solver.
+    fn validate(&self, _validator: &mut Validator) {}
+
+    /// Adds the constraints of the rule. Each clue (a cage, an arrow, ...) should be started
+    /// with `Encoder::begin_instance` so that conflicts can be attributed to it.
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError>;
+}
+
+impl Puzzle {
+    /// Returns all rules of the puzzle: the built-in ones which are present followed by the
+    /// custom ones.
+    pub fn rules(&self) -> Vec<&dyn Rule> {
+        let mut rules: Vec<&dyn Rule> = vec![&self.given_numbers];
+
+        if let Some(blocks) = &self.blocks {
+            rules.push(blocks);
+        }
+        if let Some(odd_even) = &self.odd_even {
+            rules.push(odd_even);
+        }
+        if let Some(non_consecutive) = &self.non_consecutive {
+            rules.push(non_consecutive);
+        }
+        if let Some(xv) = &self.xv {
+            rules.push(xv);
+        }
+        if let Some(diagonal) = &self.diagonal {
+            rules.push(diagonal);
+        }
+        if let Some(arrow) = &self.arrow {
+            rules.push(arrow);
+        }
+        if let Some(thermo) = &self.thermo {
+            rules.push(thermo);
+        }
+        if let Some(killer) = &self.killer {
+            rules.push(killer);
+        }
+        if let Some(consecutive) = &self.consecutive {
+            rules.push(consecutive);
+        }
+        if let Some(skyscrapers) = &self.skyscrapers {
+            rules.push(skyscrapers);
+        }
+        if let Some(x_sums) = &self.x_sums {
+            rules.push(x_sums);
+        }
+        if let Some(extra_regions) = &self.extra_regions {
+            rules.push(extra_regions);
+        }
+        if let Some(palindrome) = &self.palindrome {
+            rules.push(palindrome);
+        }
+        if let Some(forbidden_candidates) = &self.forbidden_candidates {
+            rules.push(forbidden_candidates);
+        }
+        if let Some(anti_knight) = &self.anti_knight {
+            rules.push(anti_knight);
+        }
+        if let Some(no_touch) = &self.no_touch {
+            rules.push(no_touch);
+        }
+
+        rules.extend(self.custom_rules.iter().map(|rule| rule.as_ref()));
+        rules
+    }
+}
+
+/// The keys of the rules stored in the dedicated fields of `Puzzle`.
+pub const BUILTIN_RULES: &[&str] = &[
+    "givenNumbers",
+    "blocks",
+    "oddEven",
+    "nonConsecutive",
+    "xv",
+    "diagonal",
+    "arrow",
+    "thermo",
+    "killer",
+    "consecutive",
+    "skyscrapers",
+    "xSums",
+    "extraRegions",
+    "palindrome",
+    "forbiddenCandidates",
+    "antiKnight",
+    "noTouch",
+];
+
+type RuleParser = Box<dyn Fn(serde_json::Value) -> serde_json::Result<Arc<dyn Rule>> + Send + Sync>;
+
+/// Maps the keys of custom rules to parsers. The built-in rules (see `BUILTIN_RULES`) are
+/// always parsed into the fields of `Puzzle` and cannot be registered.
+#[derive(Default)]
+pub struct RuleRegistry {
+    parsers: BTreeMap<String, RuleParser>,
+}
+
+impl RuleRegistry {
+    /// Registers the rule type `R` under `key`, replacing any rule previously registered
+    /// under the same key.
+    ///
+    /// Panics if `key` is one of `BUILTIN_RULES`.
+    pub fn register<R: Rule + DeserializeOwned + 'static>(&mut self, key: &str) {
+        assert!(
+            !BUILTIN_RULES.contains(&key),
+            "the built-in rule {} cannot be registered",
+            key
+        );
+        self.parsers.insert(
+            key.to_string(),
+            Box::new(|value| Ok(Arc::new(serde_json::from_value::<R>(value)?) as Arc<dyn Rule>)),
+        );
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.parsers.contains_key(key)
+    }
+
+    /// Parses the data of the rule registered under `key`. Returns `None` for unknown keys.
+    pub fn parse_rule(
+        &self,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Option<Result<Arc<dyn Rule>, SolverError>> {
+        let parser = self.parsers.get(key)?;
+        Some(
+            parser(value)
+                .map_err(|err| SolverError::new(ErrorCode::ParseError, Some(key), err.to_string())),
+        )
+    }
+
+    /// Parses a puzzle JSON, upgrading it to the current format version first (see
+    /// `Puzzle`'s `Deserialize` impl). Keys which are not built-in rules are looked up in the
+    /// registry and stored in `Puzzle::custom_rules`; keys unknown to the registry are ignored
+    /// (the editor also sends data of rules without solver support, e.g. `answer`).
+    pub fn parse_puzzle(&self, puzzle_json: &[u8]) -> Result<Puzzle, SolverError> {
+        let mut puzzle_json: serde_json::Value = serde_json::from_slice(puzzle_json)
+            .map_err(|err| SolverError::parse_error(err.to_string()))?;
+        // Migrating here keeps the error code of migration errors.
+        migrate::migrate(&mut puzzle_json)?;
+        let mut puzzle: Puzzle = serde_json::from_value(puzzle_json)
+            .map_err(|err| SolverError::parse_error(err.to_string()))?;
+
+        for (key, value) in &puzzle.extra {
+            if let Some(rule) = self.parse_rule(key, value.clone()) {
+                puzzle.custom_rules.push(rule?);
+            }
+        }
+
+        Ok(puzzle)
+    }
+}
+
+impl Rule for GivenNumbers {
+    fn key(&self) -> &str {
+        "givenNumbers"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_given_numbers(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_constraints_given_numbers(enc, self)
+    }
+}
+
+impl Rule for Blocks {
+    fn key(&self) -> &str {
+        "blocks"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_blocks(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_constraints_blocks(enc, self)
+    }
+}
+
+impl Rule for OddEven {
+    fn key(&self) -> &str {
+        "oddEven"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_odd_even(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_constraints_odd_even(enc, self)
+    }
+}
+
+impl Rule for NonConsecutive {
+    fn key(&self) -> &str {
+        "nonConsecutive"
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_non_consecutive_constraints(enc, self);
+        Ok(())
+    }
+}
+
+impl Rule for XV {
+    fn key(&self) -> &str {
+        "xv"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_xv(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_xv_constraints(enc, self)
+    }
+}
+
+impl Rule for Diagonal {
+    fn key(&self) -> &str {
+        "diagonal"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_diagonal(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_diagonal_constraints(enc, self)
+    }
+}
+
+impl Rule for Arrow {
+    fn key(&self) -> &str {
+        "arrow"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_arrow(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_arrow_constraints(enc, self)
+    }
+}
+
+impl Rule for Thermo {
+    fn key(&self) -> &str {
+        "thermo"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_thermo(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_thermo_constraints(enc, self)
+    }
+}
+
+impl Rule for Killer {
+    fn key(&self) -> &str {
+        "killer"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_killer(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_killer_constraints(enc, self)
+    }
+}
+
+impl Rule for Consecutive {
+    fn key(&self) -> &str {
+        "consecutive"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_consecutive(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_consecutive_constraints(enc, self)
+    }
+}
+
+impl Rule for Skyscrapers {
+    fn key(&self) -> &str {
+        "skyscrapers"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_skyscrapers(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_skyscrapers_constraints(enc, self)
+    }
+}
+
+impl Rule for XSums {
+    fn key(&self) -> &str {
+        "xSums"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_x_sums(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_xsums_constraints(enc, self)
+    }
+}
+
+impl Rule for ExtraRegions {
+    fn key(&self) -> &str {
+        "extraRegions"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_extra_regions(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_extra_regions_constraints(enc, self)
+    }
+}
+
+impl Rule for Palindrome {
+    fn key(&self) -> &str {
+        "palindrome"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_palindrome(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_palindrome_constraints(enc, self)
+    }
+}
+
+impl Rule for ForbiddenCandidates {
+    fn key(&self) -> &str {
+        "forbiddenCandidates"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validate::validate_forbidden_candidates(validator, self);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_forbidden_candidates_constraints(enc, self)
+    }
+}
+
+impl Rule for AntiKnight {
+    fn key(&self) -> &str {
+        "antiKnight"
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_anti_knight_constraints(enc);
+        Ok(())
+    }
+}
+
+impl Rule for NoTouch {
+    fn key(&self) -> &str {
+        "noTouch"
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        solver::add_no_touch_constraints(enc);
+        Ok(())
+    }
+}
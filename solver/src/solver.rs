@@ -1,18 +1,50 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use serde::Serialize;
 
+use crate::error::{ErrorCode, SolverError};
 use crate::puzzle::{
     Arrow, Blocks, Consecutive, Diagonal, ExtraRegions, ForbiddenCandidates, GivenNumbers, Killer,
-    NonConsecutive, OddEven, Palindrome, Puzzle, Skyscrapers, Thermo, XSums, ODDEVEN_EVEN,
+    NonConsecutive, OddEven, Palindrome, Pos, Puzzle, Skyscrapers, Thermo, XSums, ODDEVEN_EVEN,
     ODDEVEN_NO_CONSTRAINT, ODDEVEN_ODD, XV, XV_NO_CONSTRAINT, XV_V, XV_X,
 };
 
 use cspuz_rs::complex_constraints::sum_all_different;
-use cspuz_rs::solver::{int_constant, Config, IntExpr, IntVarArray1D, IntVarArray2D, Solver};
+use cspuz_rs::solver::{
+    all, any, int_constant, BoolExpr, Config, IntExpr, IntVarArray1D, IntVarArray2D, Solver,
+};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct SolverConfig {
     pub optimize_polarity: bool,
     pub explicit_set_encoding: bool,
+
+    /// Stop `irrefutable_facts` after roughly this much time and return the facts known so
+    /// far. The limit is checked between SAT calls, so a single hard call may overrun it.
+    ///
+    /// There is no conflict budget: the backend does not expose one, so a single SAT call can
+    /// only be bounded by splitting the search, not interrupted.
+    pub time_limit: Option<Duration>,
+
+    /// Stop `irrefutable_facts` as soon as possible once this flag is set (e.g. from another
+    /// thread when the puzzle is edited again). Checked like `time_limit`.
+    ///
+    /// The wasm build runs on the thread of the caller, so nothing can set the flag during a
+    /// call from JS; the editor relies on `time_limit` there.
+    pub cancel: Option<Arc<AtomicBool>>,
+
+    /// The encodings of the constraint families; `None` leaves the choice to
+    /// `SolverConfig::resolve`.
+    pub sum_encoding: Option<SumEncoding>,
+    pub order_encoding: Option<OrderEncoding>,
+    pub adjacency_encoding: Option<AdjacencyEncoding>,
+
+    /// Explain each eliminated candidate in `IrrefutableFacts::explanations`. This takes a
+    /// conflict search per eliminated candidate and can be much slower than solving.
+    pub explain_eliminations: bool,
 }
 
 impl Default for SolverConfig {
@@ -20,8 +52,132 @@ impl Default for SolverConfig {
         SolverConfig {
             optimize_polarity: true,
             explicit_set_encoding: true,
+            time_limit: None,
+            cancel: None,
+            sum_encoding: None,
+            order_encoding: None,
+            adjacency_encoding: None,
+            explain_eliminations: false,
+        }
+    }
+}
+
+impl SolverConfig {
+    /// Returns the encodings to use for `puzzle`: the ones set in the config, and for the
+    /// others the ones expected to work best. The thresholds come from the bench rows named
+    /// below, comparing the column of each encoding with `expl_set`.
+    pub fn resolve(&self, puzzle: &Puzzle) -> Encodings {
+        let num_digits = puzzle.digits().len();
+
+        // The number of digit combinations of a cage grows quickly with its size, while a
+        // linear sum does not propagate much on small cages. Combinations win on the cages of
+        // `few_clues_killer` (up to 4 cells) and lose on `killer_big_cages` (7 and 8 cells)
+        // and on `killer_16x16`.
+        let max_cage_size = puzzle
+            .killer
+            .iter()
+            .flat_map(|killer| &killer.regions)
+            .filter(|region| region.sum.is_some())
+            .map(|region| region.cells.len())
+            .max()
+            .unwrap_or(0);
+        let sum = self
+            .sum_encoding
+            .unwrap_or(if num_digits > 9 || max_cage_size > 6 {
+                SumEncoding::Linear
+            } else {
+                SumEncoding::Combination
+            });
+
+        // The direct encoding of skyscraper visibility is quadratic in both the line length
+        // and the number of digits (`few_clues_skyscrapers`), and thermometers on 16 digits
+        // (`thermo_16x16`) already favor the order encoding; on 9x9 thermometers
+        // (`few_clues_thermo`) the direct one is ahead.
+        let order =
+            self.order_encoding
+                .unwrap_or(if puzzle.skyscrapers.is_some() || num_digits > 9 {
+                    OrderEncoding::Order
+                } else {
+                    OrderEncoding::Direct
+                });
+
+        // Tables have a clause per digit and grow past the arithmetic encoding on 16 digits
+        // (`noncon_16x16`), while they are ahead on 9x9 (`few_clues_xv`, `few_clues_noncon*`).
+        let adjacency = self.adjacency_encoding.unwrap_or(if num_digits > 9 {
+            AdjacencyEncoding::Arithmetic
+        } else {
+            AdjacencyEncoding::Table
+        });
+
+        Encodings {
+            sum,
+            order,
+            adjacency,
+        }
+    }
+}
+
+/// The encodings used for a puzzle, as chosen by `SolverConfig::resolve`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encodings {
+    pub sum: SumEncoding,
+    pub order: OrderEncoding,
+    pub adjacency: AdjacencyEncoding,
+}
+
+/// How sums of distinct digits (killer cages with a sum, X-sums) are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SumEncoding {
+    /// A linear sum, plus an all-different constraint on killer cages with distinct digits.
+    Linear,
+    /// The digit combinations achieving the sum (`sum_all_different`).
+    Combination,
+}
+
+/// How orderings of cells (thermometers, skyscrapers) are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderEncoding {
+    /// Integer comparisons, which the backend translates on the order encoding of the cells.
+    Order,
+    /// Clauses over the literals `cell == d` (direct encoding).
+    Direct,
+}
+
+/// How relations between adjacent cells (XV, consecutive, non-consecutive) are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdjacencyEncoding {
+    /// Arithmetic constraints such as `a + b == 10`.
+    Arithmetic,
+    /// For each digit of one cell, the digits allowed in the other one.
+    Table,
+}
+
+/// The time and cancellation limits of a single solver call.
+pub(crate) struct Budget {
+    deadline: Option<Instant>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Budget {
+    pub(crate) fn new(config: &SolverConfig) -> Budget {
+        Budget {
+            deadline: config.time_limit.map(|limit| Instant::now() + limit),
+            cancel: config.cancel.clone(),
         }
     }
+
+    pub(crate) fn is_limited(&self) -> bool {
+        self.deadline.is_some() || self.cancel.is_some()
+    }
+
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
@@ -29,29 +185,96 @@ pub struct IrrefutableFacts {
     #[serde(rename = "decidedNumbers")]
     pub decided_nums: Vec<Vec<Option<i32>>>,
 
+    /// The digit set of the puzzle, in ascending order.
+    pub digits: Vec<i32>,
+
+    /// `candidates[y][x][v]` tells whether the digit `v` is possible at (y, x). Values which
+    /// are not in `digits` are never possible. Indexed by value like
+    /// `ForbiddenCandidates::is_forbidden`.
     #[serde(rename = "candidates")]
     pub candidates: Vec<Vec<Vec<bool>>>,
+
+    /// Whether the time limit was reached or the computation was cancelled. If so, the facts
+    /// are sound but incomplete: some impossible candidates may still be reported as possible.
+    #[serde(rename = "timedOut")]
+    pub timed_out: bool,
+
+    /// The reasons of the eliminated candidates, if `SolverConfig::explain_eliminations` is
+    /// set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanations: Option<Vec<Elimination>>,
 }
 
-pub fn irrefutable_facts(puzzle: &Puzzle, config: SolverConfig) -> Option<IrrefutableFacts> {
-    let n = puzzle.size;
+/// The reason why a candidate is impossible.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Elimination {
+    pub cell: Pos,
+    pub digit: i32,
 
-    let csp_config = Config {
-        optimize_polarity: config.optimize_polarity,
-        ..Config::default()
-    };
+    /// A set of rule instances which together forbid `digit` at `cell`. It is subset-minimal
+    /// (no instance can be dropped), but a smaller set may exist.
+    pub reason: Vec<RuleInstance>,
+}
 
-    let mut solver = Solver::with_config(csp_config);
-    let nums = &solver.int_var_2d((n, n), 1, n as i32);
-    solver.add_answer_key_int(nums);
+/// A single clue or house of a puzzle, e.g. one given number, one arrow or one row.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
+pub struct RuleInstance {
+    /// The JSON key of the rule, or `row` / `column` for the implicit houses.
+    pub rule: String,
+
+    /// The index of the instance within the rule. List-based rules (arrows, cages, ...) use
+    /// the index in the list, cell-based rules use `y * width + x`, border-based rules use
+    /// `border_index` and outside clues use `outside_clue_index`.
+    pub index: usize,
+
+    /// The cells the instance refers to. Empty for rules applying to the whole board.
+    pub cells: Vec<Pos>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SolveResult {
+    Satisfiable(IrrefutableFacts),
+
+    /// The puzzle has no solution. `conflict` is a minimal set of rule instances which are
+    /// already contradictory by themselves.
+    Unsatisfiable {
+        conflict: Vec<RuleInstance>,
+
+        /// Whether the time limit was reached while minimizing the conflict, in which case
+        /// `conflict` may not be minimal.
+        #[serde(rename = "timedOut")]
+        timed_out: bool,
+    },
+}
+
+/// Computes the facts shared by all solutions of `puzzle`.
+///
+/// Returns `Err` if the puzzle data is inconsistent (e.g. a rule whose shape does not match the
+/// board size). If `config` has a time limit or a cancellation flag, the result may be partial;
+/// see `IrrefutableFacts::timed_out`.
+#[allow(clippy::needless_range_loop)]
+pub fn irrefutable_facts(
+    puzzle: &Puzzle,
+    config: SolverConfig,
+) -> Result<SolveResult, SolverError> {
+    let budget = Budget::new(&config);
+    if budget.is_limited() {
+        return search_candidates(puzzle, &config, &budget, None);
+    }
+
+    let (h, w) = (puzzle.height(), puzzle.width());
+    let digits = puzzle.digits();
+    let (mut solver, nums, instances) = build_model(puzzle, &config, None)?;
+    solver.add_answer_key_int(&nums);
 
     let mut indicators = vec![];
-    for y in 0..n {
+    for y in 0..h {
         let mut row = vec![];
-        for x in 0..n {
-            let v = solver.bool_var_1d(n);
-            for i in 0..n {
-                solver.add_expr(v.at(i).iff(nums.at((y, x)).eq(i as i32 + 1)));
+        for x in 0..w {
+            let v = solver.bool_var_1d(digits.len());
+            for (i, &d) in digits.iter().enumerate() {
+                solver.add_expr(v.at(i).iff(nums.at((y, x)).eq(d)));
             }
             solver.add_answer_key_bool(&v);
             row.push(v);
@@ -59,192 +282,896 @@ pub fn irrefutable_facts(puzzle: &Puzzle, config: SolverConfig) -> Option<Irrefu
         indicators.push(row);
     }
 
-    add_constraints(&mut solver, nums, puzzle, config);
+    let Some(res) = solver.irrefutable_facts() else {
+        return unsatisfiable(puzzle, &config, &budget, &instances, None);
+    };
+    let decided_nums = res.get(&nums);
+    let mut candidates = vec![];
+    for y in 0..h {
+        let mut row = vec![];
+        for x in 0..w {
+            let mut cell = vec![false; digits[digits.len() - 1] as usize + 1];
+            for (i, &d) in digits.iter().enumerate() {
+                cell[d as usize] = res.get(&indicators[y][x].at(i)) != Some(false);
+            }
+            row.push(cell);
+        }
+        candidates.push(row);
+    }
+
+    let mut facts = IrrefutableFacts {
+        decided_nums,
+        digits,
+        candidates,
+        timed_out: false,
+        explanations: None,
+    };
+    if config.explain_eliminations {
+        explain_facts(puzzle, &config, &budget, None, &mut facts)?;
+    }
+    Ok(SolveResult::Satisfiable(facts))
+}
+
+pub(crate) fn unsatisfiable(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    budget: &Budget,
+    instances: &[RuleInstance],
+    enabled: Option<InstanceFilter>,
+) -> Result<SolveResult, SolverError> {
+    let active = (0..instances.len())
+        .filter(|&i| enabled.is_none_or(|enabled| enabled(i, &instances[i])))
+        .collect::<Vec<_>>();
+    let (conflict, complete) =
+        find_conflict(puzzle, config, budget, &active, instances.len(), None)?;
+    Ok(SolveResult::Unsatisfiable {
+        conflict: conflict.into_iter().map(|i| instances[i].clone()).collect(),
+        timed_out: !complete,
+    })
+}
+
+/// A hypothesis about a single cell, checked together with a set of rule instances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Assumption {
+    /// `digit` is placed at the cell. Inconsistency explains an elimination.
+    Place(Pos, i32),
+
+    /// `digit` is not placed at the cell. Inconsistency explains a placement.
+    Exclude(Pos, i32),
+}
+
+impl Assumption {
+    fn add_to(self, solver: &mut Solver, nums: &IntVarArray2D) {
+        match self {
+            Assumption::Place(pos, digit) => solver.add_expr(nums.at((pos.y, pos.x)).eq(digit)),
+            Assumption::Exclude(pos, digit) => solver.add_expr(nums.at((pos.y, pos.x)).ne(digit)),
+        }
+    }
+}
+
+/// Adds the explanations of all eliminated candidates to `facts`. If `budget` runs out, the
+/// remaining candidates are left unexplained and `facts.timed_out` is set.
+pub(crate) fn explain_facts(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    budget: &Budget,
+    enabled: Option<InstanceFilter>,
+    facts: &mut IrrefutableFacts,
+) -> Result<(), SolverError> {
+    let mut explanations = vec![];
+
+    'cells: for (y, row) in facts.candidates.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            for &digit in &facts.digits {
+                if cell[digit as usize] {
+                    continue;
+                }
+                if budget.is_exhausted() {
+                    facts.timed_out = true;
+                    break 'cells;
+                }
+                let pos = Pos { x, y };
+                let assumption = Assumption::Place(pos, digit);
+                if let Some(reason) = explain(puzzle, config, budget, enabled, Some(assumption))? {
+                    explanations.push(Elimination {
+                        cell: pos,
+                        digit,
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+
+    facts.explanations = Some(explanations);
+    Ok(())
+}
+
+/// Returns a minimal set of rule instances which is inconsistent together with `assumption`, or
+/// `None` if the puzzle has a solution satisfying `assumption`.
+pub(crate) fn explain(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    budget: &Budget,
+    enabled: Option<InstanceFilter>,
+    assumption: Option<Assumption>,
+) -> Result<Option<Vec<RuleInstance>>, SolverError> {
+    let (mut solver, nums, instances) = build_model(puzzle, config, enabled)?;
+    if let Some(assumption) = assumption {
+        assumption.add_to(&mut solver, &nums);
+    }
+    if solver.solve().is_some() {
+        return Ok(None);
+    }
+
+    let active = (0..instances.len())
+        .filter(|&i| enabled.is_none_or(|enabled| enabled(i, &instances[i])))
+        .collect::<Vec<_>>();
+    let (conflict, _) =
+        find_conflict(puzzle, config, budget, &active, instances.len(), assumption)?;
+    Ok(Some(
+        conflict.into_iter().map(|i| instances[i].clone()).collect(),
+    ))
+}
+
+/// Finds a minimal subset of `candidates`, rule instances given as indices into the instance
+/// list of `build_model`, which has no solution together with `assumption`, assuming that all of
+/// `candidates` has none.
+///
+/// The backend offers no assumption interface, so each consistency check encodes the selected
+/// instances into a fresh solver; QuickXplain keeps the number of checks small when the
+/// conflict is small.
+///
+/// Once `budget` is exhausted, the remaining checks answer "consistent" without solving. Since
+/// QuickXplain only drops instances on "inconsistent" answers, the result is still a conflict,
+/// though not necessarily a minimal one. The returned flag tells whether all checks were
+/// solved, i.e. whether the conflict is minimal.
+pub(crate) fn find_conflict(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    budget: &Budget,
+    candidates: &[usize],
+    num_instances: usize,
+    assumption: Option<Assumption>,
+) -> Result<(Vec<usize>, bool), SolverError> {
+    let mut complete = true;
+    let mut is_consistent = |subset: &[usize]| -> Result<bool, SolverError> {
+        if budget.is_exhausted() {
+            complete = false;
+            return Ok(true);
+        }
+        let mut enabled = vec![false; num_instances];
+        for &i in subset {
+            enabled[i] = true;
+        }
+        let (mut solver, nums, _) = build_model(puzzle, config, Some(&|i, _| enabled[i]))?;
+        if let Some(assumption) = assumption {
+            assumption.add_to(&mut solver, &nums);
+        }
+        Ok(solver.solve().is_some())
+    };
+
+    // QuickXplain assumes that the background (here `assumption` alone) is consistent.
+    if assumption.is_some() && !is_consistent(&[])? {
+        return Ok((vec![], true));
+    }
+    let mut conflict = quick_xplain(&[], false, candidates, &mut is_consistent)?;
+    conflict.sort();
+    Ok((conflict, complete))
+}
+
+/// QuickXplain (Junker, 2004): returns a minimal subset of `candidates` which is inconsistent
+/// together with `background`, provided that `background` plus all of `candidates` is.
+fn quick_xplain<F>(
+    background: &[usize],
+    has_delta: bool,
+    candidates: &[usize],
+    is_consistent: &mut F,
+) -> Result<Vec<usize>, SolverError>
+where
+    F: FnMut(&[usize]) -> Result<bool, SolverError>,
+{
+    if has_delta && !is_consistent(background)? {
+        return Ok(vec![]);
+    }
+    if candidates.len() <= 1 {
+        return Ok(candidates.to_vec());
+    }
+
+    let (c1, c2) = candidates.split_at(candidates.len() / 2);
+    let d2 = quick_xplain(&[background, c1].concat(), true, c2, is_consistent)?;
+    let d1 = quick_xplain(
+        &[background, &d2].concat(),
+        !d2.is_empty(),
+        c1,
+        is_consistent,
+    )?;
+    Ok([d1, d2].concat())
+}
+
+/// Computes the irrefutable facts by asking the solver repeatedly for a solution which places
+/// some candidate no solution found so far has placed. Once there is none, the remaining
+/// candidates are impossible.
+///
+/// Unlike `Solver::irrefutable_facts`, this checks `budget` between SAT calls. Eliminations are
+/// only proven when a query becomes unsatisfiable, so with a limited budget the board is
+/// processed row by row, each row with a fresh model; the candidates of the rows not finished
+/// in time are all reported as possible.
+pub(crate) fn search_candidates(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    budget: &Budget,
+    enabled: Option<InstanceFilter>,
+) -> Result<SolveResult, SolverError> {
+    let (h, w) = (puzzle.height(), puzzle.width());
+    let digits = puzzle.digits();
+
+    let groups = if budget.is_limited() {
+        (0..h)
+            .map(|y| (0..w).map(|x| (y, x)).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    } else {
+        vec![(0..h).flat_map(|y| (0..w).map(move |x| (y, x))).collect()]
+    };
+
+    // possible[y][x][i]: some solution found so far has digits[i] at (y, x)
+    let mut possible = vec![vec![vec![false; digits.len()]; w]; h];
+    let mark_possible = |possible: &mut [Vec<Vec<bool>>], answer: &[Vec<i32>]| {
+        for (y, row) in answer.iter().enumerate() {
+            for (x, &n) in row.iter().enumerate() {
+                if let Ok(i) = digits.binary_search(&n) {
+                    possible[y][x][i] = true;
+                }
+            }
+        }
+    };
+    let mut found_any = false;
+
+    let mut finished = vec![vec![false; w]; h];
+    'groups: for (group_index, group) in groups.iter().enumerate() {
+        let (mut solver, nums, instances) = build_model(puzzle, config, enabled)?;
+
+        loop {
+            let mut unseen = vec![];
+            for &(y, x) in group {
+                for (i, &d) in digits.iter().enumerate() {
+                    if !possible[y][x][i] {
+                        unseen.push(nums.at((y, x)).eq(d));
+                    }
+                }
+            }
+            if unseen.is_empty() {
+                break;
+            }
+            if budget.is_exhausted() {
+                break 'groups;
+            }
+            solver.add_expr(any(unseen));
+
+            let Some(model) = solver.solve() else {
+                break;
+            };
+            let answer = model.get(&nums);
+            mark_possible(&mut possible, &answer);
+            found_any = true;
+        }
+
+        // The first query asks for any solution.
+        if group_index == 0 && !found_any {
+            return unsatisfiable(puzzle, config, budget, &instances, enabled);
+        }
+        for &(y, x) in group {
+            finished[y][x] = true;
+        }
+    }
+
+    facts_from_search(puzzle, config, budget, enabled, &possible, &finished)
+}
 
-    let res = solver.irrefutable_facts()?;
-    let decided_nums = res.get(nums);
+/// Builds the result of a candidate search. `possible[y][x][i]` tells whether some solution has
+/// `digits[i]` at (y, x); the candidates of the cells which are not `finished` are all reported
+/// as possible.
+pub(crate) fn facts_from_search(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    budget: &Budget,
+    enabled: Option<InstanceFilter>,
+    possible: &[Vec<Vec<bool>>],
+    finished: &[Vec<bool>],
+) -> Result<SolveResult, SolverError> {
+    let (h, w) = (puzzle.height(), puzzle.width());
+    let digits = puzzle.digits();
+
+    let mut decided_nums = vec![];
     let mut candidates = vec![];
-    for y in 0..n {
+    for y in 0..h {
+        let mut decided_row = vec![];
         let mut row = vec![];
-        for x in 0..n {
-            let mut cell = vec![];
-            for i in 0..n {
-                cell.push(res.get(&indicators[y][x].at(i)) != Some(false));
+        for x in 0..w {
+            let mut cell = vec![false; digits[digits.len() - 1] as usize + 1];
+            for (i, &d) in digits.iter().enumerate() {
+                cell[d as usize] = !finished[y][x] || possible[y][x][i];
             }
+            let mut cell_digits = digits.iter().filter(|&&d| cell[d as usize]);
+            decided_row.push(match (cell_digits.next(), cell_digits.next()) {
+                (Some(&d), None) => Some(d),
+                _ => None,
+            });
             row.push(cell);
         }
+        decided_nums.push(decided_row);
         candidates.push(row);
     }
 
-    Some(IrrefutableFacts {
+    let mut facts = IrrefutableFacts {
         decided_nums,
+        digits,
         candidates,
+        timed_out: finished.iter().flatten().any(|&f| !f),
+        explanations: None,
+    };
+    if config.explain_eliminations {
+        explain_facts(puzzle, config, budget, enabled, &mut facts)?;
+    }
+    Ok(SolveResult::Satisfiable(facts))
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SolutionCount {
+    pub count: usize,
+
+    /// Whether the search stopped after `limit` solutions. If so, `count` is a lower bound.
+    #[serde(rename = "limitReached")]
+    pub limit_reached: bool,
+}
+
+/// Counts the solutions of `puzzle`, stopping once `limit` solutions have been found. A `limit`
+/// of 0 is rejected with an `OutOfRange` error.
+pub fn count_solutions(
+    puzzle: &Puzzle,
+    limit: usize,
+    config: SolverConfig,
+) -> Result<SolutionCount, SolverError> {
+    if limit == 0 {
+        return Err(SolverError::new(
+            ErrorCode::OutOfRange,
+            None,
+            "the solution limit must be positive",
+        ));
+    }
+    let count = solutions(puzzle, config)?.take(limit).count();
+
+    Ok(SolutionCount {
+        count,
+        limit_reached: count == limit,
     })
 }
 
-fn add_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+/// Iterator over the solutions of a puzzle. Created by `solutions`.
+pub struct Solutions {
+    solver: Solver<'static>,
+    nums: IntVarArray2D,
+    finished: bool,
+}
+
+impl Iterator for Solutions {
+    type Item = Vec<Vec<i32>>;
+
+    fn next(&mut self) -> Option<Vec<Vec<i32>>> {
+        if self.finished {
+            return None;
+        }
+
+        let answer = match self.solver.solve() {
+            Some(model) => model.get(&self.nums),
+            None => {
+                self.finished = true;
+                return None;
+            }
+        };
+
+        // Block this solution so that the next call finds a different one.
+        let mut differs = vec![];
+        for (y, row) in answer.iter().enumerate() {
+            for (x, &n) in row.iter().enumerate() {
+                differs.push(self.nums.at((y, x)).ne(n));
+            }
+        }
+        self.solver.add_expr(any(differs));
+
+        Some(answer)
+    }
+}
+
+/// Enumerates the solutions of `puzzle` as full grids of numbers.
+pub fn solutions(puzzle: &Puzzle, config: SolverConfig) -> Result<Solutions, SolverError> {
+    let (solver, nums, _) = build_model(puzzle, &config, None)?;
+
+    Ok(Solutions {
+        solver,
+        nums,
+        finished: false,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Ambiguity {
+    /// Two distinct solutions of the puzzle.
+    pub solutions: [Vec<Vec<i32>>; 2],
+
+    /// The cells where the two solutions differ.
+    #[serde(rename = "differingCells")]
+    pub differing_cells: Vec<Pos>,
+}
+
+/// Finds two distinct solutions of `puzzle`. Returns `None` if the puzzle has at most one
+/// solution.
+pub fn find_ambiguity(
     puzzle: &Puzzle,
     config: SolverConfig,
-) {
-    add_constraints_rows_columns(solver, nums, config);
+) -> Result<Option<Ambiguity>, SolverError> {
+    let mut solutions = solutions(puzzle, config)?;
+    let (Some(first), Some(second)) = (solutions.next(), solutions.next()) else {
+        return Ok(None);
+    };
 
-    add_constraints_given_numbers(solver, nums, &puzzle.given_numbers, config);
+    let mut differing_cells = vec![];
+    for y in 0..first.len() {
+        for x in 0..first[y].len() {
+            if first[y][x] != second[y][x] {
+                differing_cells.push(Pos { x, y });
+            }
+        }
+    }
 
-    if let Some(blocks) = &puzzle.blocks {
-        add_constraints_blocks(solver, nums, blocks, config);
+    Ok(Some(Ambiguity {
+        solutions: [first, second],
+        differing_cells,
+    }))
+}
+
+/// Encodes `puzzle` into a fresh solver.
+///
+/// If `enabled` is given, only the rule instances for which it returns true are encoded. It is
+/// called with the position of the instance in the returned list and the instance itself.
+pub(crate) fn build_model(
+    puzzle: &Puzzle,
+    config: &SolverConfig,
+    enabled: Option<InstanceFilter>,
+) -> Result<(Solver<'static>, IntVarArray2D, Vec<RuleInstance>), SolverError> {
+    let (h, w) = (puzzle.height(), puzzle.width());
+    if h == 0 || w == 0 {
+        return Err(SolverError::new(
+            ErrorCode::UnsupportedValue,
+            None,
+            "board size must be positive",
+        ));
+    }
+    let digits = puzzle.digits();
+    if digits.is_empty() {
+        return Err(SolverError::new(
+            ErrorCode::UnsupportedValue,
+            None,
+            "the digit set must not be empty",
+        ));
+    }
+    if digits[0] < 0 {
+        return Err(SolverError::new(
+            ErrorCode::UnsupportedValue,
+            None,
+            "digits must be non-negative",
+        ));
     }
 
-    if let Some(odd_even) = &puzzle.odd_even {
-        add_constraints_odd_even(solver, nums, odd_even, config);
+    let csp_config = Config {
+        optimize_polarity: config.optimize_polarity,
+        ..Config::default()
+    };
+
+    let mut solver = Solver::with_config(csp_config);
+    let (low, high) = (digits[0], digits[digits.len() - 1]);
+    let nums = solver.int_var_2d((h, w), low, high);
+    for v in low..=high {
+        if digits.binary_search(&v).is_err() {
+            for y in 0..h {
+                for x in 0..w {
+                    solver.add_expr(nums.at((y, x)).ne(v));
+                }
+            }
+        }
     }
 
-    if let Some(non_consecutive) = &puzzle.non_consecutive {
-        add_non_consecutive_constraints(solver, nums, non_consecutive, config);
+    let house_digits = puzzle.house_digits();
+    let encodings = config.resolve(puzzle);
+    let mut encoder = Encoder::new(
+        &mut solver,
+        &nums,
+        &digits,
+        &house_digits,
+        config,
+        encodings,
+        enabled,
+    );
+    add_constraints(&mut encoder, puzzle)?;
+    let instances = encoder.instances;
+
+    Ok((solver, nums, instances))
+}
+
+/// Decides whether a rule instance is encoded, given its position in the instance list and the
+/// instance itself. See `build_model`.
+pub(crate) type InstanceFilter<'a> = &'a dyn Fn(usize, &RuleInstance) -> bool;
+
+/// Thin wrapper around `Solver` which records the rule instance each constraint belongs to and
+/// skips the constraints of disabled instances. Rules add their constraints through this type
+/// (see `Rule::encode`).
+pub struct Encoder<'s, 'a> {
+    solver: &'s mut Solver<'a>,
+    nums: &'s IntVarArray2D,
+    digits: &'s [i32],
+    house_digits: &'s [i32],
+    config: &'s SolverConfig,
+    encodings: Encodings,
+    enabled: Option<InstanceFilter<'s>>,
+    instances: Vec<RuleInstance>,
+    active: bool,
+}
+
+impl<'s, 'a> Encoder<'s, 'a> {
+    fn new(
+        solver: &'s mut Solver<'a>,
+        nums: &'s IntVarArray2D,
+        digits: &'s [i32],
+        house_digits: &'s [i32],
+        config: &'s SolverConfig,
+        encodings: Encodings,
+        enabled: Option<InstanceFilter<'s>>,
+    ) -> Encoder<'s, 'a> {
+        Encoder {
+            solver,
+            nums,
+            digits,
+            house_digits,
+            config,
+            encodings,
+            enabled,
+            instances: vec![],
+            active: true,
+        }
     }
 
-    if let Some(xv) = &puzzle.xv {
-        add_xv_constraints(solver, nums, xv, config);
+    /// The number in each cell of the board.
+    pub fn nums(&self) -> &'s IntVarArray2D {
+        self.nums
     }
 
-    if let Some(diagonal) = &puzzle.diagonal {
-        add_diagonal_constraints(solver, nums, diagonal, config);
+    /// The digits which can be placed in cells, in ascending order.
+    pub fn digits(&self) -> &'s [i32] {
+        self.digits
     }
 
-    if let Some(arrow_constraints) = &puzzle.arrow {
-        add_arrow_constraints(solver, nums, arrow_constraints, config);
+    /// The multiset of digits each house must contain, in ascending order.
+    pub fn house_digits(&self) -> &'s [i32] {
+        self.house_digits
     }
 
-    if let Some(thermo_constraints) = &puzzle.thermo {
-        add_thermo_constraints(solver, nums, thermo_constraints, config);
+    pub fn config(&self) -> &'s SolverConfig {
+        self.config
     }
 
-    if let Some(killer_constraints) = &puzzle.killer {
-        add_killer_constraints(solver, nums, killer_constraints, config);
+    /// The encodings chosen for the puzzle (see `SolverConfig::resolve`).
+    pub fn encodings(&self) -> Encodings {
+        self.encodings
     }
 
-    if let Some(consecutive) = &puzzle.consecutive {
-        add_consecutive_constraints(solver, nums, consecutive, config);
+    /// Starts a new rule instance. Constraints added until the next call belong to it.
+    pub fn begin_instance(&mut self, rule: &str, index: usize, cells: Vec<Pos>) {
+        let instance = RuleInstance {
+            rule: rule.to_string(),
+            index,
+            cells,
+        };
+        self.active = self
+            .enabled
+            .is_none_or(|enabled| enabled(self.instances.len(), &instance));
+        self.instances.push(instance);
     }
 
-    if let Some(skyscrapers) = &puzzle.skyscrapers {
-        add_skyscrapers_constraints(solver, nums, skyscrapers, config);
+    /// Gives direct access to the solver for constraints not covered by the methods below
+    /// (e.g. auxiliary variables). Returns `None` if the current instance is disabled.
+    pub fn solver(&mut self) -> Option<&mut Solver<'a>> {
+        if self.active {
+            Some(self.solver)
+        } else {
+            None
+        }
     }
 
-    if let Some(x_sums) = &puzzle.x_sums {
-        add_xsums_constraints(solver, nums, x_sums, config);
+    pub fn add_expr(&mut self, expr: BoolExpr) {
+        if self.active {
+            self.solver.add_expr(expr);
+        }
     }
 
-    if let Some(extra_regions) = &puzzle.extra_regions {
-        add_extra_regions_constraints(solver, nums, extra_regions, config);
+    pub fn all_different(&mut self, cells: &[(usize, usize)]) {
+        if self.active {
+            self.solver.all_different(self.nums.select(cells));
+        }
+    }
+
+    pub fn sum_all_different(
+        &mut self,
+        cells: IntVarArray1D,
+        sum: i32,
+        low: i32,
+        high: i32,
+        cond: Option<BoolExpr>,
+    ) {
+        if self.active {
+            sum_all_different(self.solver, cells, sum, low, high, cond);
+        }
     }
+}
+
+/// Index of the border between (y, x) and its lower (`vertical == false`) or right
+/// (`vertical == true`) neighbor among the instances of a border-based rule.
+pub fn border_index(y: usize, x: usize, vertical: bool, h: usize, w: usize) -> usize {
+    if vertical {
+        (h - 1) * w + y * (w - 1) + x
+    } else {
+        y * w + x
+    }
+}
+
+/// Index of an outside clue among the instances of an outside-clue rule. Clues are numbered
+/// up, down, left, right in this order.
+pub fn outside_clue_index(side: usize, i: usize, h: usize, w: usize) -> usize {
+    match side {
+        0 => i,
+        1 => w + i,
+        2 => 2 * w + i,
+        _ => 2 * w + h + i,
+    }
+}
 
-    if let Some(palindrome_constraints) = &puzzle.palindrome {
-        add_palindrome_constraints(solver, nums, palindrome_constraints, config);
+fn to_pos(cells: &[(usize, usize)]) -> Vec<Pos> {
+    cells.iter().map(|&(y, x)| Pos { x, y }).collect()
+}
+
+fn add_constraints(enc: &mut Encoder, puzzle: &Puzzle) -> Result<(), SolverError> {
+    add_constraints_rows_columns(enc);
+
+    for rule in puzzle.rules() {
+        rule.encode(enc)?;
     }
 
-    if let Some(forbidden_candidates) = &puzzle.forbidden_candidates {
-        add_forbidden_candidates_constraints(solver, nums, forbidden_candidates);
+    Ok(())
+}
+
+fn check_len(rule: &str, field: &str, actual: usize, expected: usize) -> Result<(), SolverError> {
+    if actual != expected {
+        return Err(SolverError::shape_mismatch(
+            rule,
+            format!("{} has length {} (expected {})", field, actual, expected),
+        ));
     }
+    Ok(())
+}
 
-    if puzzle.anti_knight.is_some() {
-        add_anti_knight_constraints(solver, nums, config);
+/// Checks that `grid`, the field `field` of `rule`, has `height` rows of `width` entries.
+pub fn check_grid<T>(
+    rule: &str,
+    field: &str,
+    grid: &[Vec<T>],
+    height: usize,
+    width: usize,
+) -> Result<(), SolverError> {
+    check_len(rule, field, grid.len(), height)?;
+    for (y, row) in grid.iter().enumerate() {
+        check_len(rule, &format!("{}[{}]", field, y), row.len(), width)?;
     }
+    Ok(())
+}
+
+fn cell_of(rule: &str, pos: &Pos, nums: &IntVarArray2D) -> Result<(usize, usize), SolverError> {
+    let (h, w) = nums.shape();
+    check_cell(rule, pos, h, w)
+}
 
-    if puzzle.no_touch.is_some() {
-        add_no_touch_constraints(solver, nums, config);
+/// Checks that `pos` is on an `h` x `w` board, returning it as (y, x).
+pub fn check_cell(
+    rule: &str,
+    pos: &Pos,
+    h: usize,
+    w: usize,
+) -> Result<(usize, usize), SolverError> {
+    if pos.y >= h || pos.x >= w {
+        return Err(SolverError::out_of_range(
+            rule,
+            format!("cell (y: {}, x: {}) is outside the board", pos.y, pos.x),
+        ));
     }
+    Ok((pos.y, pos.x))
 }
 
-fn add_complete_set(
-    solver: &mut Solver,
+/// Checks that `cells`, the cells of an instance of `rule`, are on the board of `nums`,
+/// returning them as (y, x).
+pub fn cells_of(
+    rule: &str,
+    cells: &[Pos],
     nums: &IntVarArray2D,
-    cells: &[(usize, usize)],
-    explicit_set_encoding: bool,
+) -> Result<Vec<(usize, usize)>, SolverError> {
+    cells.iter().map(|pos| cell_of(rule, pos, nums)).collect()
+}
+
+/// Constrains the digits `(a, b)` of two cells to satisfy `allowed`. `arithmetic` is the same
+/// constraint as an expression, used with `AdjacencyEncoding::Arithmetic`.
+fn add_pair_constraint(
+    enc: &mut Encoder,
+    a: (usize, usize),
+    b: (usize, usize),
+    arithmetic: BoolExpr,
+    allowed: impl Fn(i32, i32) -> bool,
 ) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
-    assert_eq!(cells.len(), h);
+    let nums = enc.nums();
+    match enc.encodings().adjacency {
+        AdjacencyEncoding::Arithmetic => enc.add_expr(arithmetic),
+        AdjacencyEncoding::Table => {
+            let digits = enc.digits();
+            for &d in digits {
+                let partners = digits
+                    .iter()
+                    .filter(|&&e| allowed(d, e))
+                    .map(|&e| nums.at(b).eq(e))
+                    .collect::<Vec<_>>();
+                enc.add_expr(nums.at(a).ne(d) | any(partners));
+            }
+        }
+    }
+}
+
+/// Constrains the digit of `a` to be less than that of `b`.
+fn add_less_than(enc: &mut Encoder, a: (usize, usize), b: (usize, usize)) {
+    let nums = enc.nums();
+    match enc.encodings().order {
+        OrderEncoding::Order => enc.add_expr(nums.at(b).gt(nums.at(a))),
+        OrderEncoding::Direct => {
+            let digits = enc.digits();
+            for &d in digits {
+                let smaller = digits
+                    .iter()
+                    .filter(|&&e| e < d)
+                    .map(|&e| nums.at(a).eq(e))
+                    .collect::<Vec<_>>();
+                enc.add_expr(nums.at(b).ne(d) | any(smaller));
+            }
+        }
+    }
+}
+
+/// Adds the constraint of a house (row, column, block, ...) which must contain the multiset
+/// `required` of digits.
+///
+/// A house with exactly `required.len()` cells contains each digit as many times as it appears
+/// in `required`, and no other digit. A smaller house contains a sub-multiset of `required`,
+/// while a larger one contains at least `required` and may take any digit in the other cells.
+fn add_house(enc: &mut Encoder, cells: &[(usize, usize)], required: &[i32]) {
+    let nums = enc.nums();
+    let mut counts = BTreeMap::<i32, usize>::new();
+    for &d in required {
+        *counts.entry(d).or_default() += 1;
+    }
+
+    if cells.len() <= required.len() {
+        if counts.values().all(|&c| c == 1) {
+            enc.all_different(cells);
+        }
+        for &(y, x) in cells {
+            for &d in enc.digits() {
+                if !counts.contains_key(&d) {
+                    enc.add_expr(nums.at((y, x)).ne(d));
+                }
+            }
+        }
+    }
 
-    // TODO: some variants may use the number set other than {1, 2, ..., n}
+    let exact = cells.len() == required.len();
     let cells = &nums.select(cells);
-    solver.all_different(cells);
 
-    for n in 1..=(h as i32) {
-        if explicit_set_encoding {
-            let indicators = &cells.eq(n);
-            solver.add_expr(indicators.any());
+    for (&d, &c) in &counts {
+        let indicators = &cells.eq(d);
+        if exact && c == 1 && enc.config().explicit_set_encoding {
+            enc.add_expr(indicators.any());
 
             for i in 0..indicators.len() {
                 for j in 0..i {
-                    solver.add_expr(!(indicators.at(i) & indicators.at(j)));
+                    enc.add_expr(!(indicators.at(i) & indicators.at(j)));
                 }
             }
+        } else if exact {
+            enc.add_expr(indicators.count_true().eq(c as i32));
+        } else if cells.len() < required.len() {
+            if c > 1 && c < cells.len() {
+                enc.add_expr(indicators.count_true().le(c as i32));
+            }
+        } else if c == 1 {
+            enc.add_expr(indicators.any());
         } else {
-            solver.add_expr(cells.eq(n).count_true().eq(1));
+            enc.add_expr(indicators.count_true().ge(c as i32));
         }
     }
 }
 
-fn add_constraints_rows_columns(solver: &mut Solver, nums: &IntVarArray2D, config: SolverConfig) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
+fn add_constraints_rows_columns(enc: &mut Encoder) {
+    let (h, w) = enc.nums().shape();
+    let house_digits = enc.house_digits();
 
     for y in 0..h {
         let cells = (0..w).map(|x| (y, x)).collect::<Vec<_>>();
-        add_complete_set(solver, nums, &cells, config.explicit_set_encoding);
+        enc.begin_instance("row", y, to_pos(&cells));
+        add_house(enc, &cells, house_digits);
     }
 
     for x in 0..w {
         let cells = (0..h).map(|y| (y, x)).collect::<Vec<_>>();
-        add_complete_set(solver, nums, &cells, config.explicit_set_encoding);
+        enc.begin_instance("column", x, to_pos(&cells));
+        add_house(enc, &cells, house_digits);
     }
 }
 
-fn add_constraints_given_numbers(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_constraints_given_numbers(
+    enc: &mut Encoder,
     given_numbers: &GivenNumbers,
-    _config: SolverConfig,
-) {
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(given_numbers.numbers.len(), h);
+    check_grid("givenNumbers", "numbers", &given_numbers.numbers, h, w)?;
 
     for y in 0..h {
-        assert_eq!(given_numbers.numbers[y].len(), w);
-
         for x in 0..w {
             if let Some(n) = given_numbers.numbers[y][x] {
-                solver.add_expr(nums.at((y, x)).eq(n));
+                enc.begin_instance("givenNumbers", y * w + x, vec![Pos { x, y }]);
+                enc.add_expr(nums.at((y, x)).eq(n));
             }
         }
     }
+
+    Ok(())
 }
 
-fn add_constraints_blocks(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_constraints_blocks(
+    enc: &mut Encoder,
     blocks: &Blocks,
-    config: SolverConfig,
-) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
-    let n = h;
-
-    assert_eq!(blocks.horizontal.len(), n - 1);
-    for y in 0..(n - 1) {
-        assert_eq!(blocks.horizontal[y].len(), n);
+) -> Result<(), SolverError> {
+    let (h, w) = enc.nums().shape();
+    let house_digits = enc.house_digits();
+
+    for (block_index, cells) in block_regions(blocks, h, w, house_digits.len())?
+        .into_iter()
+        .enumerate()
+    {
+        enc.begin_instance("blocks", block_index, to_pos(&cells));
+        add_house(enc, &cells, house_digits);
     }
 
-    assert_eq!(blocks.vertical.len(), n);
-    for x in 0..n {
-        assert_eq!(blocks.vertical[x].len(), n - 1);
-    }
+    Ok(())
+}
 
-    let mut visited = vec![vec![false; n]; n];
-    for y in 0..n {
-        for x in 0..n {
+/// Returns the regions delimited by the borders of `blocks` which consist of `size` cells, in
+/// the order of their numbering as `blocks` rule instances.
+pub(crate) fn block_regions(
+    blocks: &Blocks,
+    h: usize,
+    w: usize,
+    size: usize,
+) -> Result<Vec<Vec<(usize, usize)>>, SolverError> {
+    check_grid("blocks", "horizontalBorder", &blocks.horizontal, h - 1, w)?;
+    check_grid("blocks", "verticalBorder", &blocks.vertical, h, w - 1)?;
+
+    let mut regions = vec![];
+    let mut visited = vec![vec![false; w]; h];
+    for y in 0..h {
+        for x in 0..w {
             if visited[y][x] {
                 continue;
             }
@@ -259,10 +1186,10 @@ fn add_constraints_blocks(
                 visited[y][x] = true;
                 cells.push((y, x));
 
-                if y < n - 1 && !blocks.horizontal[y][x] {
+                if y < h - 1 && !blocks.horizontal[y][x] {
                     queue.push((y + 1, x));
                 }
-                if x < n - 1 && !blocks.vertical[y][x] {
+                if x < w - 1 && !blocks.vertical[y][x] {
                     queue.push((y, x + 1));
                 }
                 if y > 0 && !blocks.horizontal[y - 1][x] {
@@ -273,385 +1200,535 @@ fn add_constraints_blocks(
                 }
             }
 
-            if cells.len() == n {
-                add_complete_set(solver, nums, &cells, config.explicit_set_encoding);
+            if cells.len() == size {
+                regions.push(cells);
             }
         }
     }
+
+    Ok(regions)
 }
 
-fn add_constraints_odd_even(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_constraints_odd_even(
+    enc: &mut Encoder,
     odd_even: &OddEven,
-    _config: SolverConfig,
-) {
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
-    assert_eq!(odd_even.cell_kind.len(), h);
+    check_grid("oddEven", "cellKind", &odd_even.cell_kind, h, w)?;
 
     for y in 0..h {
-        assert_eq!(odd_even.cell_kind[y].len(), w);
-
         for x in 0..w {
             let kind = odd_even.cell_kind[y][x];
             if kind == ODDEVEN_NO_CONSTRAINT {
                 continue;
             }
+            if kind != ODDEVEN_ODD && kind != ODDEVEN_EVEN {
+                return Err(SolverError::unsupported_value(
+                    "oddEven",
+                    format!("unknown cell kind {}", kind),
+                ));
+            }
 
-            for n in 1..=(h as i32) {
-                if kind == ODDEVEN_ODD && !(n % 2 == 1) {
-                    solver.add_expr(nums.at((y, x)).ne(n));
+            enc.begin_instance("oddEven", y * w + x, vec![Pos { x, y }]);
+            for &n in enc.digits() {
+                if kind == ODDEVEN_ODD && n.rem_euclid(2) != 1 {
+                    enc.add_expr(nums.at((y, x)).ne(n));
                 }
-                if kind == ODDEVEN_EVEN && !(n % 2 == 0) {
-                    solver.add_expr(nums.at((y, x)).ne(n));
+                if kind == ODDEVEN_EVEN && n.rem_euclid(2) != 0 {
+                    enc.add_expr(nums.at((y, x)).ne(n));
                 }
             }
         }
     }
+
+    Ok(())
 }
 
-fn add_non_consecutive_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn is_consecutive(a: i32, b: i32) -> bool {
+    (a - b).abs() == 1
+}
+
+fn not_consecutive(a: i32, b: i32) -> bool {
+    !is_consecutive(a, b)
+}
+
+pub(crate) fn add_non_consecutive_constraints(
+    enc: &mut Encoder,
     _non_consecutive: &NonConsecutive,
-    _config: SolverConfig,
 ) {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
 
+    enc.begin_instance("nonConsecutive", 0, vec![]);
     for y in 0..h {
         for x in 0..w {
             let a = nums.at((y, x));
             if x > 0 {
                 let b = &nums.at((y, x - 1));
-                solver.add_expr(a.ne(b - 1));
-                solver.add_expr(a.ne(b + 1));
+                let arithmetic = a.ne(b - 1) & a.ne(b + 1);
+                add_pair_constraint(enc, (y, x), (y, x - 1), arithmetic, not_consecutive);
             }
 
             if y > 0 {
                 let b = &nums.at((y - 1, x));
-                solver.add_expr(a.ne(b - 1));
-                solver.add_expr(a.ne(b + 1));
+                let arithmetic = a.ne(b - 1) & a.ne(b + 1);
+                add_pair_constraint(enc, (y, x), (y - 1, x), arithmetic, not_consecutive);
             }
         }
     }
 }
 
-fn add_xv_constraints(solver: &mut Solver, nums: &IntVarArray2D, xv: &XV, _config: SolverConfig) {
+/// Adds the constraint of an XV border of the given kind between `a` and `b`.
+fn add_xv_pair(enc: &mut Encoder, a: (usize, usize), b: (usize, usize), kind: i32) {
+    let nums = enc.nums();
+    let sum = &(nums.at(a) + nums.at(b));
+    match kind {
+        XV_X => add_pair_constraint(enc, a, b, sum.eq(10), |a, b| a + b == 10),
+        XV_V => add_pair_constraint(enc, a, b, sum.eq(5), |a, b| a + b == 5),
+        _ => add_pair_constraint(enc, a, b, sum.ne(10) & sum.ne(5), |a, b| {
+            a + b != 10 && a + b != 5
+        }),
+    }
+}
+
+pub(crate) fn add_xv_constraints(enc: &mut Encoder, xv: &XV) -> Result<(), SolverError> {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
 
-    assert_eq!(xv.horizontal.len(), h - 1);
-    for y in 0..(h - 1) {
-        assert_eq!(xv.horizontal[y].len(), w);
+    check_grid("xv", "horizontalBorder", &xv.horizontal, h - 1, w)?;
+    check_grid("xv", "verticalBorder", &xv.vertical, h, w - 1)?;
 
+    for y in 0..(h - 1) {
         for x in 0..w {
             let kind = xv.horizontal[y][x];
+            enc.begin_instance(
+                "xv",
+                border_index(y, x, false, h, w),
+                vec![Pos { x, y }, Pos { x, y: y + 1 }],
+            );
 
-            match kind {
-                XV_NO_CONSTRAINT => {
-                    solver.add_expr((nums.at((y, x)) + nums.at((y + 1, x))).ne(10));
-                    solver.add_expr((nums.at((y, x)) + nums.at((y + 1, x))).ne(5));
-                }
-                XV_X => solver.add_expr((nums.at((y, x)) + nums.at((y + 1, x))).eq(10)),
-                XV_V => solver.add_expr((nums.at((y, x)) + nums.at((y + 1, x))).eq(5)),
-                _ => panic!(),
+            if kind != XV_NO_CONSTRAINT && kind != XV_X && kind != XV_V {
+                return Err(SolverError::unsupported_value(
+                    "xv",
+                    format!("unknown XV kind {}", kind),
+                ));
+            }
+            if kind != XV_NO_CONSTRAINT || xv.all_shown {
+                add_xv_pair(enc, (y, x), (y + 1, x), kind);
             }
         }
     }
 
-    assert_eq!(xv.vertical.len(), h);
     for y in 0..h {
-        assert_eq!(xv.vertical[y].len(), w - 1);
-
         for x in 0..(w - 1) {
             let kind = xv.vertical[y][x];
+            enc.begin_instance(
+                "xv",
+                border_index(y, x, true, h, w),
+                vec![Pos { x, y }, Pos { x: x + 1, y }],
+            );
 
-            match kind {
-                XV_NO_CONSTRAINT => {
-                    if xv.all_shown {
-                        solver.add_expr((nums.at((y, x)) + nums.at((y, x + 1))).ne(10));
-                        solver.add_expr((nums.at((y, x)) + nums.at((y, x + 1))).ne(5));
-                    }
-                }
-                XV_X => solver.add_expr((nums.at((y, x)) + nums.at((y, x + 1))).eq(10)),
-                XV_V => solver.add_expr((nums.at((y, x)) + nums.at((y, x + 1))).eq(5)),
-                _ => panic!(),
+            if kind != XV_NO_CONSTRAINT && kind != XV_X && kind != XV_V {
+                return Err(SolverError::unsupported_value(
+                    "xv",
+                    format!("unknown XV kind {}", kind),
+                ));
+            }
+            if kind != XV_NO_CONSTRAINT || xv.all_shown {
+                add_xv_pair(enc, (y, x), (y, x + 1), kind);
             }
         }
     }
+
+    Ok(())
 }
 
-fn add_diagonal_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_diagonal_constraints(
+    enc: &mut Encoder,
     diagonal: &Diagonal,
-    config: SolverConfig,
-) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
+) -> Result<(), SolverError> {
+    let (h, w) = enc.nums().shape();
+    let house_digits = enc.house_digits();
+    if h != w && (diagonal.main_diagonal || diagonal.anti_diagonal) {
+        return Err(SolverError::unsupported_value(
+            "diagonal",
+            "diagonals are only supported on square boards",
+        ));
+    }
 
     if diagonal.main_diagonal {
-        add_complete_set(
-            solver,
-            nums,
-            &(0..h).map(|i| (i, i)).collect::<Vec<_>>(),
-            config.explicit_set_encoding,
-        );
+        let cells = (0..h).map(|i| (i, i)).collect::<Vec<_>>();
+        enc.begin_instance("diagonal", 0, to_pos(&cells));
+        add_house(enc, &cells, house_digits);
     }
 
     if diagonal.anti_diagonal {
-        add_complete_set(
-            solver,
-            nums,
-            &(0..h).map(|i| (i, h - 1 - i)).collect::<Vec<_>>(),
-            config.explicit_set_encoding,
-        );
+        let cells = (0..h).map(|i| (i, h - 1 - i)).collect::<Vec<_>>();
+        enc.begin_instance("diagonal", 1, to_pos(&cells));
+        add_house(enc, &cells, house_digits);
     }
+
+    Ok(())
 }
 
-fn add_arrow_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_arrow_constraints(
+    enc: &mut Encoder,
     arrow_constraints: &Arrow,
-    _config: SolverConfig,
-) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
-
-    for arrow in &arrow_constraints.arrows {
-        assert!(arrow.len() >= 1);
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
+
+    for (i, arrow) in arrow_constraints.arrows.iter().enumerate() {
+        if arrow.is_empty() {
+            return Err(SolverError::unsupported_value(
+                "arrow",
+                "an arrow must have at least one cell",
+            ));
+        }
+        let cells = cells_of("arrow", arrow, nums)?;
+        enc.begin_instance("arrow", i, arrow.clone());
 
         let mut non_head_sum = int_constant(0);
-        for i in 1..arrow.len() {
-            non_head_sum = non_head_sum + nums.at((arrow[i].y, arrow[i].x));
+        for &cell in &cells[1..] {
+            non_head_sum = non_head_sum + nums.at(cell);
         }
 
-        solver.add_expr(nums.at((arrow[0].y, arrow[0].x)).eq(non_head_sum));
+        enc.add_expr(nums.at(cells[0]).eq(non_head_sum));
     }
+
+    Ok(())
 }
 
-fn add_thermo_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_thermo_constraints(
+    enc: &mut Encoder,
     thermo_constraints: &Thermo,
-    _config: SolverConfig,
-) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
-
-    for thermo in &thermo_constraints.thermos {
-        for i in 1..thermo.len() {
-            solver.add_expr(
-                nums.at((thermo[i].y, thermo[i].x))
-                    .gt(nums.at((thermo[i - 1].y, thermo[i - 1].x))),
-            );
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
+
+    for (i, thermo) in thermo_constraints.thermos.iter().enumerate() {
+        let cells = cells_of("thermo", thermo, nums)?;
+        enc.begin_instance("thermo", i, thermo.clone());
+        for i in 1..cells.len() {
+            add_less_than(enc, cells[i - 1], cells[i]);
         }
     }
+
+    Ok(())
 }
 
-fn add_killer_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_killer_constraints(
+    enc: &mut Encoder,
     killer_constraints: &Killer,
-    _config: SolverConfig,
-) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
+    let digits = enc.digits();
+    let (low, high) = (digits[0], digits[digits.len() - 1]);
 
-    for region in &killer_constraints.regions {
-        if killer_constraints.distinct {
-            let mut cells = vec![];
-            for cell in &region.cells {
-                cells.push(nums.at((cell.y, cell.x)));
-            }
+    for (i, region) in killer_constraints.regions.iter().enumerate() {
+        let cells = cells_of("killer", &region.cells, nums)?;
+        enc.begin_instance("killer", i, region.cells.clone());
 
-            if let Some(sum_value) = region.sum {
-                sum_all_different(solver, cells, sum_value, 1, h as i32, None);
-            } else {
-                solver.all_different(cells);
+        if killer_constraints.distinct {
+            match (region.sum, enc.encodings().sum) {
+                (Some(sum_value), SumEncoding::Combination) => {
+                    enc.sum_all_different(nums.select(&cells), sum_value, low, high, None);
+                }
+                (Some(sum_value), SumEncoding::Linear) => {
+                    enc.all_different(&cells);
+                    let mut sum = int_constant(0);
+                    for &cell in &cells {
+                        sum = sum + nums.at(cell);
+                    }
+                    enc.add_expr(sum.eq(sum_value));
+                }
+                (None, _) => enc.all_different(&cells),
             }
         } else {
             let mut sum = int_constant(0);
-            for cell in &region.cells {
-                sum = sum + nums.at((cell.y, cell.x));
+            for &cell in &cells {
+                sum = sum + nums.at(cell);
             }
 
             if let Some(sum_value) = region.sum {
-                solver.add_expr(sum.eq(sum_value));
+                enc.add_expr(sum.eq(sum_value));
             }
         }
     }
+
+    Ok(())
 }
 
-fn add_consecutive_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_consecutive_constraints(
+    enc: &mut Encoder,
     consecutive: &Consecutive,
-    _config: SolverConfig,
-) {
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
+
+    check_grid(
+        "consecutive",
+        "horizontalBorder",
+        &consecutive.horizontal,
+        h - 1,
+        w,
+    )?;
+    check_grid(
+        "consecutive",
+        "verticalBorder",
+        &consecutive.vertical,
+        h,
+        w - 1,
+    )?;
 
     for y in 0..h {
         for x in 0..w {
             if y < h - 1 {
                 let a = nums.at((y, x));
                 let b = &nums.at((y + 1, x));
+                enc.begin_instance(
+                    "consecutive",
+                    border_index(y, x, false, h, w),
+                    vec![Pos { x, y }, Pos { x, y: y + 1 }],
+                );
                 if consecutive.horizontal[y][x] {
-                    solver.add_expr(a.eq(b - 1) | a.eq(b + 1));
+                    let arithmetic = a.eq(b - 1) | a.eq(b + 1);
+                    add_pair_constraint(enc, (y, x), (y + 1, x), arithmetic, is_consecutive);
                 } else if consecutive.all_shown {
-                    solver.add_expr(a.ne(b - 1));
-                    solver.add_expr(a.ne(b + 1));
+                    let arithmetic = a.ne(b - 1) & a.ne(b + 1);
+                    add_pair_constraint(enc, (y, x), (y + 1, x), arithmetic, not_consecutive);
                 }
             }
 
             if x < w - 1 {
                 let a = nums.at((y, x));
                 let b = &nums.at((y, x + 1));
+                enc.begin_instance(
+                    "consecutive",
+                    border_index(y, x, true, h, w),
+                    vec![Pos { x, y }, Pos { x: x + 1, y }],
+                );
                 if consecutive.vertical[y][x] {
-                    solver.add_expr(a.eq(b - 1) | a.eq(b + 1));
+                    let arithmetic = a.eq(b - 1) | a.eq(b + 1);
+                    add_pair_constraint(enc, (y, x), (y, x + 1), arithmetic, is_consecutive);
                 } else if consecutive.all_shown {
-                    solver.add_expr(a.ne(b - 1));
-                    solver.add_expr(a.ne(b + 1));
+                    let arithmetic = a.ne(b - 1) & a.ne(b + 1);
+                    add_pair_constraint(enc, (y, x), (y, x + 1), arithmetic, not_consecutive);
                 }
             }
         }
     }
+
+    Ok(())
 }
 
-fn skyscrapers_num_seen(seq: &IntVarArray1D) -> IntExpr {
+fn skyscrapers_num_seen(seq: &IntVarArray1D, encoding: OrderEncoding, digits: &[i32]) -> IntExpr {
+    // Both encodings only count a cell strictly higher than all cells before it, so a repeated
+    // digit (with `houseDigits` being a multiset) is correctly hidden behind the first one.
     let mut ret = int_constant(0);
     for i in 0..seq.len() {
-        ret = ret + seq.at(i).gt(seq.slice(..i)).all().ite(1, 0);
+        let visible = match encoding {
+            OrderEncoding::Order => seq.at(i).gt(seq.slice(..i)).all(),
+            OrderEncoding::Direct => any(digits.iter().map(|&d| {
+                let mut conds = vec![seq.at(i).eq(d)];
+                for j in 0..i {
+                    conds.push(any(digits
+                        .iter()
+                        .filter(|&&e| e < d)
+                        .map(|&e| seq.at(j).eq(e))));
+                }
+                all(conds)
+            })),
+        };
+        ret = ret + visible.ite(1, 0);
     }
     ret
 }
 
-fn add_skyscrapers_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+/// Returns the cells seen from the given side of the board, nearest first.
+fn outside_line(side: usize, i: usize, h: usize, w: usize) -> Vec<(usize, usize)> {
+    match side {
+        0 => (0..h).map(|y| (y, i)).collect(),
+        1 => (0..h).rev().map(|y| (y, i)).collect(),
+        2 => (0..w).map(|x| (i, x)).collect(),
+        _ => (0..w).rev().map(|x| (i, x)).collect(),
+    }
+}
+
+pub(crate) fn add_skyscrapers_constraints(
+    enc: &mut Encoder,
     skyscrapers: &Skyscrapers,
-    _config: SolverConfig,
-) {
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
 
-    for i in 0..h {
-        if let Some(n) = skyscrapers.up[i] {
-            solver.add_expr(skyscrapers_num_seen(&nums.slice_fixed_x((.., i))).eq(n));
-        }
-        if let Some(n) = skyscrapers.down[i] {
-            solver.add_expr(skyscrapers_num_seen(&nums.slice_fixed_x((.., i)).reverse()).eq(n));
-        }
-        if let Some(n) = skyscrapers.left[i] {
-            solver.add_expr(skyscrapers_num_seen(&nums.slice_fixed_y((i, ..))).eq(n));
-        }
-        if let Some(n) = skyscrapers.right[i] {
-            solver.add_expr(skyscrapers_num_seen(&nums.slice_fixed_y((i, ..)).reverse()).eq(n));
+    check_len("skyscrapers", "up", skyscrapers.up.len(), w)?;
+    check_len("skyscrapers", "down", skyscrapers.down.len(), w)?;
+    check_len("skyscrapers", "left", skyscrapers.left.len(), h)?;
+    check_len("skyscrapers", "right", skyscrapers.right.len(), h)?;
+
+    let clues = [
+        &skyscrapers.up,
+        &skyscrapers.down,
+        &skyscrapers.left,
+        &skyscrapers.right,
+    ];
+    for (side, clues) in clues.into_iter().enumerate() {
+        for (i, &clue) in clues.iter().enumerate() {
+            let Some(n) = clue else {
+                continue;
+            };
+            let cells = outside_line(side, i, h, w);
+            enc.begin_instance(
+                "skyscrapers",
+                outside_clue_index(side, i, h, w),
+                to_pos(&cells),
+            );
+            let num_seen =
+                skyscrapers_num_seen(&nums.select(&cells), enc.encodings().order, enc.digits());
+            enc.add_expr(num_seen.eq(n));
         }
     }
+
+    Ok(())
 }
 
-fn xsums_single_constraint(solver: &mut Solver, seq: &IntVarArray1D, v: i32, size: usize) {
-    for i in 1..=size {
-        sum_all_different(
-            solver,
-            seq.slice(..i),
-            v,
-            1,
-            size as i32,
-            Some(seq.at(0).eq(i as i32)),
-        );
+fn xsums_single_constraint(enc: &mut Encoder, seq: &IntVarArray1D, v: i32) {
+    let digits = enc.digits();
+    let (low, high) = (digits[0], digits[digits.len() - 1]);
+    // `sum_all_different` relies on the digits of a line being distinct, which a multiset
+    // `houseDigits` does not guarantee.
+    let distinct = enc.house_digits().windows(2).all(|p| p[0] != p[1]);
+
+    for &d in digits {
+        if d >= 1 && d as usize <= seq.len() {
+            let cells = seq.slice(..(d as usize));
+            match enc.encodings().sum {
+                SumEncoding::Combination if distinct => {
+                    enc.sum_all_different(cells, v, low, high, Some(seq.at(0).eq(d)));
+                }
+                _ => {
+                    // A plain sum, which needs no distinctness: the row or column constraint
+                    // already makes the cells distinct when `houseDigits` has no repeated
+                    // digit, and they need not be otherwise.
+                    let mut sum = int_constant(0);
+                    for i in 0..cells.len() {
+                        sum = sum + cells.at(i);
+                    }
+                    enc.add_expr(seq.at(0).ne(d) | sum.eq(v));
+                }
+            }
+        } else if !(d == 0 && v == 0) {
+            // The first `d` cells do not exist, except that a 0 sums up no cells.
+            enc.add_expr(seq.at(0).ne(d));
+        }
     }
 }
 
-fn add_xsums_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
-    xsums: &XSums,
-    _config: SolverConfig,
-) {
+pub(crate) fn add_xsums_constraints(enc: &mut Encoder, xsums: &XSums) -> Result<(), SolverError> {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
 
-    for i in 0..h {
-        if let Some(n) = xsums.up[i] {
-            xsums_single_constraint(solver, &nums.slice_fixed_x((.., i)), n, h);
-        }
-        if let Some(n) = xsums.down[i] {
-            xsums_single_constraint(solver, &nums.slice_fixed_x((.., i)).reverse(), n, h);
-        }
-        if let Some(n) = xsums.left[i] {
-            xsums_single_constraint(solver, &nums.slice_fixed_y((i, ..)), n, w);
-        }
-        if let Some(n) = xsums.right[i] {
-            xsums_single_constraint(solver, &nums.slice_fixed_y((i, ..)).reverse(), n, w);
+    check_len("xSums", "up", xsums.up.len(), w)?;
+    check_len("xSums", "down", xsums.down.len(), w)?;
+    check_len("xSums", "left", xsums.left.len(), h)?;
+    check_len("xSums", "right", xsums.right.len(), h)?;
+
+    let clues = [&xsums.up, &xsums.down, &xsums.left, &xsums.right];
+    for (side, clues) in clues.into_iter().enumerate() {
+        for (i, &clue) in clues.iter().enumerate() {
+            let Some(n) = clue else {
+                continue;
+            };
+            let cells = outside_line(side, i, h, w);
+            enc.begin_instance("xSums", outside_clue_index(side, i, h, w), to_pos(&cells));
+            xsums_single_constraint(enc, &nums.select(&cells), n);
         }
     }
+
+    Ok(())
 }
 
-fn add_extra_regions_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_extra_regions_constraints(
+    enc: &mut Encoder,
     extra_region: &ExtraRegions,
-    config: SolverConfig,
-) {
-    let (h, w) = nums.shape();
-    assert_eq!(h, w);
-
-    for region in &extra_region.regions {
-        let cells = region
-            .cells
-            .iter()
-            .map(|cell| (cell.y, cell.x))
-            .collect::<Vec<_>>();
-        if region.cells.len() == h {
-            add_complete_set(solver, nums, &cells, config.explicit_set_encoding);
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
+    let house_digits = enc.house_digits();
+
+    for (i, region) in extra_region.regions.iter().enumerate() {
+        let cells = cells_of("extraRegions", &region.cells, nums)?;
+        enc.begin_instance("extraRegions", i, region.cells.clone());
+        if let Some(digits) = &region.digits {
+            add_house(enc, &cells, digits);
+        } else if region.cells.len() == house_digits.len() {
+            add_house(enc, &cells, house_digits);
         } else {
-            solver.all_different(nums.select(&cells));
+            enc.all_different(&cells);
         }
     }
+
+    Ok(())
 }
 
-fn add_palindrome_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_palindrome_constraints(
+    enc: &mut Encoder,
     palindrome: &Palindrome,
-    _config: SolverConfig,
-) {
-    for line in &palindrome.palindromes {
-        let n = line.len();
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
+
+    for (i, line) in palindrome.palindromes.iter().enumerate() {
+        let cells = cells_of("palindrome", line, nums)?;
+        enc.begin_instance("palindrome", i, line.clone());
+        let n = cells.len();
         for i in 0..(n / 2) {
-            solver.add_expr(
-                nums.at((line[i].y, line[i].x))
-                    .eq(nums.at((line[n - 1 - i].y, line[n - 1 - i].x))),
-            );
+            enc.add_expr(nums.at(cells[i]).eq(nums.at(cells[n - 1 - i])));
         }
     }
+
+    Ok(())
 }
 
-fn add_forbidden_candidates_constraints(
-    solver: &mut Solver,
-    nums: &IntVarArray2D,
+pub(crate) fn add_forbidden_candidates_constraints(
+    enc: &mut Encoder,
     forbidden_candidates: &ForbiddenCandidates,
-) {
-    let n = nums.shape().0;
-    for y in 0..n {
-        for x in 0..n {
-            for num in 0..n {
-                if forbidden_candidates.is_forbidden[y][x][num] {
-                    solver.add_expr(nums.at((y, x)).ne((num + 1) as i32));
+) -> Result<(), SolverError> {
+    let nums = enc.nums();
+    let digits = enc.digits();
+    let (h, w) = nums.shape();
+    check_grid(
+        "forbiddenCandidates",
+        "isForbidden",
+        &forbidden_candidates.is_forbidden,
+        h,
+        w,
+    )?;
+
+    for y in 0..h {
+        for x in 0..w {
+            check_len(
+                "forbiddenCandidates",
+                &format!("isForbidden[{}][{}]", y, x),
+                forbidden_candidates.is_forbidden[y][x].len(),
+                digits[digits.len() - 1] as usize + 1,
+            )?;
+            if !forbidden_candidates.is_forbidden[y][x].contains(&true) {
+                continue;
+            }
+            enc.begin_instance("forbiddenCandidates", y * w + x, vec![Pos { x, y }]);
+            for &d in digits {
+                if forbidden_candidates.is_forbidden[y][x][d as usize] {
+                    enc.add_expr(nums.at((y, x)).ne(d));
                 }
             }
         }
     }
+
+    Ok(())
 }
 
-fn add_anti_knight_constraints(solver: &mut Solver, nums: &IntVarArray2D, _config: SolverConfig) {
+pub(crate) fn add_anti_knight_constraints(enc: &mut Encoder) {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
 
     let knight_moves = [
         (-2, -1),
@@ -664,22 +1741,23 @@ fn add_anti_knight_constraints(solver: &mut Solver, nums: &IntVarArray2D, _confi
         (-2, 1),
     ];
 
+    enc.begin_instance("antiKnight", 0, vec![]);
     for y in 0..h {
         for x in 0..w {
             for &(dy, dx) in &knight_moves {
                 let ny = y as isize + dy;
                 let nx = x as isize + dx;
                 if ny >= 0 && ny < h as isize && nx >= 0 && nx < w as isize {
-                    solver.add_expr(nums.at((y, x)).ne(nums.at((ny as usize, nx as usize))));
+                    enc.add_expr(nums.at((y, x)).ne(nums.at((ny as usize, nx as usize))));
                 }
             }
         }
     }
 }
 
-fn add_no_touch_constraints(solver: &mut Solver, nums: &IntVarArray2D, _config: SolverConfig) {
+pub(crate) fn add_no_touch_constraints(enc: &mut Encoder) {
+    let nums = enc.nums();
     let (h, w) = nums.shape();
-    assert_eq!(h, w);
     let deltas = [
         (-1, -1),
         (-1, 0),
@@ -690,6 +1768,7 @@ fn add_no_touch_constraints(solver: &mut Solver, nums: &IntVarArray2D, _config:
         (1, 0),
         (1, 1),
     ];
+    enc.begin_instance("noTouch", 0, vec![]);
     for y in 0..h {
         for x in 0..w {
             for &(dy, dx) in &deltas {
@@ -699,7 +1778,7 @@ fn add_no_touch_constraints(solver: &mut Solver, nums: &IntVarArray2D, _config:
                     if ny as usize == y && nx as usize == x {
                         continue;
                     }
-                    solver.add_expr(nums.at((y, x)).ne(nums.at((ny as usize, nx as usize))));
+                    enc.add_expr(nums.at((y, x)).ne(nums.at((ny as usize, nx as usize))));
                 }
             }
         }
@@ -0,0 +1,98 @@
+use serde::Serialize;
+
+use crate::error::SolverError;
+use crate::logic::{solve_logically, Link, LogicResult, Technique};
+use crate::puzzle::{Candidate, Pos, Puzzle};
+use crate::solver::RuleInstance;
+
+/// A logical solve in a form the editor can replay step by step.
+///
+/// The JSON format is covered by the snapshot tests in `tests/trace_snapshots.rs`; changes to it
+/// must keep older editors working.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trace {
+    /// The rule instances the steps are based on, in the order of their first use.
+    pub instances: Vec<RuleInstance>,
+
+    pub steps: Vec<TraceStep>,
+
+    /// The digits of the cells filled by the givens or the steps.
+    #[serde(rename = "decidedNumbers")]
+    pub decided_nums: Vec<Vec<Option<i32>>>,
+
+    /// Whether all cells have been filled.
+    pub solved: bool,
+}
+
+/// A single deduction of a `Trace`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TraceStep {
+    pub technique: Technique,
+
+    /// The indices in `Trace::instances` of the rule instances the deduction is based on.
+    pub instances: Vec<usize>,
+
+    /// The cells of `candidates` and `placements`, without duplicates.
+    pub cells: Vec<Pos>,
+
+    /// The candidates the deduction is based on. For chains, the candidates of the chain in
+    /// order, connected by `links`.
+    pub candidates: Vec<Candidate>,
+
+    pub links: Vec<Link>,
+
+    pub placements: Vec<Candidate>,
+
+    /// The removed candidates, including those removed from the houses of placed digits.
+    pub eliminations: Vec<Candidate>,
+}
+
+impl Trace {
+    pub fn new(result: LogicResult) -> Trace {
+        let mut instances: Vec<RuleInstance> = vec![];
+        let mut steps = vec![];
+        for step in result.steps {
+            let mut indices = vec![];
+            for instance in step.houses {
+                let index = match instances.iter().position(|i| *i == instance) {
+                    Some(index) => index,
+                    None => {
+                        instances.push(instance);
+                        instances.len() - 1
+                    }
+                };
+                indices.push(index);
+            }
+
+            let mut cells = vec![];
+            for candidate in step.candidates.iter().chain(&step.placements) {
+                if !cells.contains(&candidate.cell) {
+                    cells.push(candidate.cell);
+                }
+            }
+
+            steps.push(TraceStep {
+                technique: step.technique,
+                instances: indices,
+                cells,
+                candidates: step.candidates,
+                links: step.links,
+                placements: step.placements,
+                eliminations: step.eliminations,
+            });
+        }
+
+        Trace {
+            instances,
+            steps,
+            decided_nums: result.decided_nums,
+            solved: result.solved,
+        }
+    }
+}
+
+/// Solves `puzzle` with human techniques (see `solve_logically`) and returns the steps as a
+/// trace.
+pub fn trace_logical_solve(puzzle: &Puzzle) -> Result<Trace, SolverError> {
+    Ok(Trace::new(solve_logically(puzzle)?))
+}
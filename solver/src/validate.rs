@@ -0,0 +1,593 @@
+use serde::Serialize;
+
+use crate::error::{ErrorCode, SolverError};
+use crate::puzzle::{
+    Arrow, Blocks, Consecutive, Diagonal, ExtraRegions, ForbiddenCandidates, GivenNumbers, Killer,
+    OddEven, Palindrome, Pos, Puzzle, Skyscrapers, Thermo, XSums, ODDEVEN_EVEN,
+    ODDEVEN_NO_CONSTRAINT, ODDEVEN_ODD, XV, XV_NO_CONSTRAINT, XV_V, XV_X,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The puzzle cannot be encoded as given.
+    Error,
+    /// The puzzle can be encoded, but the rule is probably not what the author intended
+    /// (e.g. it makes the puzzle trivially unsolvable).
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+
+    /// The JSON key of the rule the diagnostic refers to.
+    pub rule: String,
+
+    /// The index of the offending instance (arrow, thermo, cage, ...) within the rule,
+    /// for rules consisting of a list of instances.
+    pub index: Option<usize>,
+
+    pub cells: Vec<Pos>,
+    pub message: String,
+}
+
+impl From<Diagnostic> for SolverError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        SolverError::new(diagnostic.code, Some(&diagnostic.rule), diagnostic.message)
+    }
+}
+
+impl Puzzle {
+    /// Checks the puzzle data without invoking the solver.
+    ///
+    /// Diagnostics of severity `Error` mark inputs the wasm exports refuse to solve (see
+    /// `first_error`). Besides the inputs the encoder rejects, they include instances which
+    /// could be encoded but are malformed, such as an arrow without a shaft or a thermometer
+    /// visiting a cell twice.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut validator = Validator {
+            height: self.height(),
+            width: self.width(),
+            digits: self.digits(),
+            house_digits: self.house_digits(),
+            diagnostics: vec![],
+        };
+        if validator.height == 0 || validator.width == 0 {
+            validator.error(
+                ErrorCode::UnsupportedValue,
+                "size",
+                None,
+                vec![],
+                "board size must be positive",
+            );
+            return validator.diagnostics;
+        }
+        if validator.digits.is_empty() {
+            validator.error(
+                ErrorCode::UnsupportedValue,
+                "digits",
+                None,
+                vec![],
+                "the digit set must not be empty",
+            );
+            return validator.diagnostics;
+        }
+        if validator.digits[0] < 0 {
+            validator.error(
+                ErrorCode::UnsupportedValue,
+                "digits",
+                None,
+                vec![],
+                "digits must be non-negative",
+            );
+            return validator.diagnostics;
+        }
+        let house_digits = validator.house_digits.clone();
+        validator.check_digit_multiset("houseDigits", None, &house_digits);
+
+        for rule in self.rules() {
+            rule.validate(&mut validator);
+        }
+        validator.diagnostics
+    }
+}
+
+/// Returns the first error-severity diagnostic of `puzzle` as a `SolverError`, if any.
+pub fn first_error(puzzle: &Puzzle) -> Option<SolverError> {
+    puzzle
+        .validate()
+        .into_iter()
+        .find(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(SolverError::from)
+}
+
+/// Collects the diagnostics reported by the rules of a puzzle. See `Rule::validate`.
+pub struct Validator {
+    height: usize,
+    width: usize,
+    digits: Vec<i32>,
+    house_digits: Vec<i32>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    /// The height of the board being validated.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The width of the board being validated.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The digit set of the puzzle, in ascending order.
+    pub fn digits(&self) -> &[i32] {
+        &self.digits
+    }
+
+    /// The multiset of digits each house must contain, in ascending order.
+    pub fn house_digits(&self) -> &[i32] {
+        &self.house_digits
+    }
+
+    pub fn report(
+        &mut self,
+        severity: Severity,
+        code: ErrorCode,
+        rule: &str,
+        index: Option<usize>,
+        cells: Vec<Pos>,
+        message: impl Into<String>,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code,
+            rule: rule.to_string(),
+            index,
+            cells,
+            message: message.into(),
+        });
+    }
+
+    pub fn error(
+        &mut self,
+        code: ErrorCode,
+        rule: &str,
+        index: Option<usize>,
+        cells: Vec<Pos>,
+        message: impl Into<String>,
+    ) {
+        self.report(Severity::Error, code, rule, index, cells, message);
+    }
+
+    pub fn warning(
+        &mut self,
+        rule: &str,
+        index: Option<usize>,
+        cells: Vec<Pos>,
+        message: impl Into<String>,
+    ) {
+        self.report(
+            Severity::Warning,
+            ErrorCode::UnsupportedValue,
+            rule,
+            index,
+            cells,
+            message,
+        );
+    }
+
+    pub fn check_len(&mut self, rule: &str, field: &str, actual: usize, expected: usize) -> bool {
+        if actual != expected {
+            self.error(
+                ErrorCode::ShapeMismatch,
+                rule,
+                None,
+                vec![],
+                format!("{} has length {} (expected {})", field, actual, expected),
+            );
+            return false;
+        }
+        true
+    }
+
+    pub fn check_grid<T>(
+        &mut self,
+        rule: &str,
+        field: &str,
+        grid: &[Vec<T>],
+        height: usize,
+        width: usize,
+    ) -> bool {
+        if !self.check_len(rule, field, grid.len(), height) {
+            return false;
+        }
+        let mut ok = true;
+        for (y, row) in grid.iter().enumerate() {
+            ok &= self.check_len(rule, &format!("{}[{}]", field, y), row.len(), width);
+        }
+        ok
+    }
+
+    /// Checks that all cells of an instance are on the board and pairwise distinct.
+    pub fn check_cells(&mut self, rule: &str, index: usize, cells: &[Pos]) -> bool {
+        let mut ok = true;
+        for (i, pos) in cells.iter().enumerate() {
+            if pos.y >= self.height || pos.x >= self.width {
+                self.error(
+                    ErrorCode::OutOfRange,
+                    rule,
+                    Some(index),
+                    vec![*pos],
+                    format!("cell (y: {}, x: {}) is outside the board", pos.y, pos.x),
+                );
+                ok = false;
+            } else if cells[..i].contains(pos) {
+                self.error(
+                    ErrorCode::UnsupportedValue,
+                    rule,
+                    Some(index),
+                    vec![*pos],
+                    format!("cell (y: {}, x: {}) appears more than once", pos.y, pos.x),
+                );
+                ok = false;
+            }
+        }
+        ok
+    }
+
+    /// Checks that a multiset of digits required in a house consists of digits of the puzzle.
+    pub fn check_digit_multiset(&mut self, rule: &str, index: Option<usize>, digits: &[i32]) {
+        for &d in digits {
+            if self.digits.binary_search(&d).is_err() {
+                self.error(
+                    ErrorCode::UnsupportedValue,
+                    rule,
+                    index,
+                    vec![],
+                    format!("digit {} is not in the digit set {:?}", d, self.digits),
+                );
+                return;
+            }
+        }
+    }
+
+    pub fn check_value(&mut self, rule: &str, pos: Pos, value: i32) {
+        if self.digits.binary_search(&value).is_err() {
+            self.error(
+                ErrorCode::UnsupportedValue,
+                rule,
+                None,
+                vec![pos],
+                format!("value {} is not in the digit set {:?}", value, self.digits),
+            );
+        }
+    }
+}
+
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn validate_given_numbers(v: &mut Validator, given_numbers: &GivenNumbers) {
+    let (h, w) = (v.height(), v.width());
+    let numbers = &given_numbers.numbers;
+    if v.check_grid("givenNumbers", "numbers", numbers, h, w) {
+        for y in 0..h {
+            for x in 0..w {
+                if let Some(value) = numbers[y][x] {
+                    v.check_value("givenNumbers", Pos { x, y }, value);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn validate_blocks(v: &mut Validator, blocks: &Blocks) {
+    let (h, w) = (v.height(), v.width());
+    v.check_grid("blocks", "horizontalBorder", &blocks.horizontal, h - 1, w);
+    v.check_grid("blocks", "verticalBorder", &blocks.vertical, h, w - 1);
+}
+
+pub(crate) fn validate_odd_even(v: &mut Validator, odd_even: &OddEven) {
+    let (h, w) = (v.height(), v.width());
+    if v.check_grid("oddEven", "cellKind", &odd_even.cell_kind, h, w) {
+        for y in 0..h {
+            for x in 0..w {
+                let kind = odd_even.cell_kind[y][x];
+                if kind != ODDEVEN_NO_CONSTRAINT && kind != ODDEVEN_ODD && kind != ODDEVEN_EVEN {
+                    v.error(
+                        ErrorCode::UnsupportedValue,
+                        "oddEven",
+                        None,
+                        vec![Pos { x, y }],
+                        format!("unknown cell kind {}", kind),
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn validate_xv(v: &mut Validator, xv: &XV) {
+    let (h, w) = (v.height(), v.width());
+    let is_valid_kind = |kind: i32| kind == XV_NO_CONSTRAINT || kind == XV_X || kind == XV_V;
+    if v.check_grid("xv", "horizontalBorder", &xv.horizontal, h - 1, w) {
+        for y in 0..(h - 1) {
+            for x in 0..w {
+                if !is_valid_kind(xv.horizontal[y][x]) {
+                    v.error(
+                        ErrorCode::UnsupportedValue,
+                        "xv",
+                        None,
+                        vec![Pos { x, y }, Pos { x, y: y + 1 }],
+                        format!("unknown XV kind {}", xv.horizontal[y][x]),
+                    );
+                }
+            }
+        }
+    }
+    if v.check_grid("xv", "verticalBorder", &xv.vertical, h, w - 1) {
+        for y in 0..h {
+            for x in 0..(w - 1) {
+                if !is_valid_kind(xv.vertical[y][x]) {
+                    v.error(
+                        ErrorCode::UnsupportedValue,
+                        "xv",
+                        None,
+                        vec![Pos { x, y }, Pos { x: x + 1, y }],
+                        format!("unknown XV kind {}", xv.vertical[y][x]),
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn validate_diagonal(v: &mut Validator, diagonal: &Diagonal) {
+    if v.height() != v.width() && (diagonal.main_diagonal || diagonal.anti_diagonal) {
+        v.error(
+            ErrorCode::UnsupportedValue,
+            "diagonal",
+            None,
+            vec![],
+            "diagonals are only supported on square boards",
+        );
+    }
+}
+
+pub(crate) fn validate_arrow(v: &mut Validator, arrow: &Arrow) {
+    for (i, cells) in arrow.arrows.iter().enumerate() {
+        if cells.len() < 2 {
+            v.error(
+                ErrorCode::UnsupportedValue,
+                "arrow",
+                Some(i),
+                cells.clone(),
+                "an arrow needs a circle and at least one shaft cell",
+            );
+            continue;
+        }
+        v.check_cells("arrow", i, cells);
+    }
+}
+
+pub(crate) fn validate_thermo(v: &mut Validator, thermo: &Thermo) {
+    let n = v.digits().len();
+    for (i, cells) in thermo.thermos.iter().enumerate() {
+        if !v.check_cells("thermo", i, cells) {
+            continue;
+        }
+        if cells.len() < 2 {
+            v.warning(
+                "thermo",
+                Some(i),
+                cells.clone(),
+                "a thermometer with fewer than 2 cells has no effect",
+            );
+        } else if cells.len() > n {
+            v.warning(
+                "thermo",
+                Some(i),
+                cells.clone(),
+                format!("a thermometer longer than {} cells cannot be filled", n),
+            );
+        }
+    }
+}
+
+pub(crate) fn validate_killer(v: &mut Validator, killer: &Killer) {
+    let digits = v.digits().to_vec();
+    let n = digits.len();
+    for (i, region) in killer.regions.iter().enumerate() {
+        if !v.check_cells("killer", i, &region.cells) {
+            continue;
+        }
+        let len = region.cells.len();
+        if len == 0 {
+            v.warning("killer", Some(i), vec![], "the cage has no cells");
+            continue;
+        }
+        if killer.distinct && len > n {
+            v.warning(
+                "killer",
+                Some(i),
+                region.cells.clone(),
+                format!(
+                    "a cage with distinct digits cannot have more than {} cells",
+                    n
+                ),
+            );
+            continue;
+        }
+        if let Some(sum) = region.sum {
+            let (min_sum, max_sum) = if killer.distinct {
+                let min_sum = digits[..len].iter().sum::<i32>();
+                let max_sum = digits[(n - len)..].iter().sum::<i32>();
+                (min_sum, max_sum)
+            } else {
+                (len as i32 * digits[0], len as i32 * digits[n - 1])
+            };
+            if sum < min_sum || sum > max_sum {
+                v.warning(
+                    "killer",
+                    Some(i),
+                    region.cells.clone(),
+                    format!(
+                        "sum {} is not achievable (expected {}..={})",
+                        sum, min_sum, max_sum
+                    ),
+                );
+            }
+        }
+    }
+}
+
+pub(crate) fn validate_consecutive(v: &mut Validator, consecutive: &Consecutive) {
+    let (h, w) = (v.height(), v.width());
+    v.check_grid(
+        "consecutive",
+        "horizontalBorder",
+        &consecutive.horizontal,
+        h - 1,
+        w,
+    );
+    v.check_grid(
+        "consecutive",
+        "verticalBorder",
+        &consecutive.vertical,
+        h,
+        w - 1,
+    );
+}
+
+pub(crate) fn validate_skyscrapers(v: &mut Validator, skyscrapers: &Skyscrapers) {
+    let (h, w) = (v.height(), v.width());
+    for (field, clues, len, n) in [
+        ("up", &skyscrapers.up, w, h),
+        ("down", &skyscrapers.down, w, h),
+        ("left", &skyscrapers.left, h, w),
+        ("right", &skyscrapers.right, h, w),
+    ] {
+        if !v.check_len("skyscrapers", field, clues.len(), len) {
+            continue;
+        }
+        for &clue in clues.iter().flatten() {
+            if clue < 1 || clue > n as i32 {
+                v.warning(
+                    "skyscrapers",
+                    None,
+                    vec![],
+                    format!("{} clue {} is not in the range 1..={}", field, clue, n),
+                );
+            }
+        }
+    }
+}
+
+pub(crate) fn validate_x_sums(v: &mut Validator, x_sums: &XSums) {
+    let (h, w) = (v.height(), v.width());
+    for (field, clues, len, n) in [
+        ("up", &x_sums.up, w, h),
+        ("down", &x_sums.down, w, h),
+        ("left", &x_sums.left, h, w),
+        ("right", &x_sums.right, h, w),
+    ] {
+        if !v.check_len("xSums", field, clues.len(), len) {
+            continue;
+        }
+        let Some((low, high)) = x_sum_range(v.house_digits(), n) else {
+            continue;
+        };
+        for &clue in clues.iter().flatten() {
+            if clue < low || clue > high {
+                v.warning(
+                    "xSums",
+                    None,
+                    vec![],
+                    format!(
+                        "{} clue {} is not in the range {}..={}",
+                        field, clue, low, high
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Returns the smallest and the largest X-sum of a line of `n` cells whose digits are taken
+/// from `house_digits` (in ascending order), or `None` if no first digit is possible.
+fn x_sum_range(house_digits: &[i32], n: usize) -> Option<(i32, i32)> {
+    let mut range: Option<(i32, i32)> = None;
+    for (i, &d) in house_digits.iter().enumerate() {
+        if d as usize > n || (i > 0 && house_digits[i - 1] == d) {
+            continue;
+        }
+        let (low, high) = if d == 0 {
+            (0, 0)
+        } else {
+            let mut rest = house_digits.to_vec();
+            rest.remove(i);
+            let k = d as usize - 1;
+            if k > rest.len() {
+                continue;
+            }
+            let low = d + rest[..k].iter().sum::<i32>();
+            let high = d + rest[rest.len() - k..].iter().sum::<i32>();
+            (low, high)
+        };
+        range = Some(match range {
+            Some((l, h)) => (l.min(low), h.max(high)),
+            None => (low, high),
+        });
+    }
+    range
+}
+
+pub(crate) fn validate_extra_regions(v: &mut Validator, extra_regions: &ExtraRegions) {
+    let n = v.digits().len();
+    let house_len = v.house_digits().len();
+    for (i, region) in extra_regions.regions.iter().enumerate() {
+        if !v.check_cells("extraRegions", i, &region.cells) {
+            continue;
+        }
+        if let Some(digits) = &region.digits {
+            v.check_digit_multiset("extraRegions", Some(i), digits);
+        } else if region.cells.len() > n && region.cells.len() != house_len {
+            v.warning(
+                "extraRegions",
+                Some(i),
+                region.cells.clone(),
+                format!("a region with more than {} cells cannot be filled", n),
+            );
+        }
+    }
+}
+
+pub(crate) fn validate_palindrome(v: &mut Validator, palindrome: &Palindrome) {
+    for (i, cells) in palindrome.palindromes.iter().enumerate() {
+        v.check_cells("palindrome", i, cells);
+    }
+}
+
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn validate_forbidden_candidates(
+    v: &mut Validator,
+    forbidden_candidates: &ForbiddenCandidates,
+) {
+    let (h, w) = (v.height(), v.width());
+    let len = v.digits()[v.digits().len() - 1] as usize + 1;
+    let is_forbidden = &forbidden_candidates.is_forbidden;
+    if v.check_grid("forbiddenCandidates", "isForbidden", is_forbidden, h, w) {
+        for y in 0..h {
+            for x in 0..w {
+                v.check_len(
+                    "forbiddenCandidates",
+                    &format!("isForbidden[{}][{}]", y, x),
+                    is_forbidden[y][x].len(),
+                    len,
+                );
+            }
+        }
+    }
+}
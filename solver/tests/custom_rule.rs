@@ -0,0 +1,129 @@
+use serde::Deserialize;
+
+use sudoku_variants_solver::error::{ErrorCode, SolverError};
+use sudoku_variants_solver::puzzle::Pos;
+use sudoku_variants_solver::rule::{Rule, RuleRegistry};
+use sudoku_variants_solver::solver::{
+    cells_of, irrefutable_facts, Encoder, SolveResult, SolverConfig,
+};
+use sudoku_variants_solver::validate::Validator;
+
+/// Each listed cell holds at least `minimum`.
+#[derive(Debug, Deserialize)]
+struct HighCells {
+    cells: Vec<Pos>,
+    minimum: i32,
+}
+
+impl Rule for HighCells {
+    fn key(&self) -> &str {
+        "highCells"
+    }
+
+    fn validate(&self, validator: &mut Validator) {
+        validator.check_cells("highCells", 0, &self.cells);
+    }
+
+    fn encode(&self, enc: &mut Encoder<'_, '_>) -> Result<(), SolverError> {
+        let nums = enc.nums();
+        let cells = cells_of("highCells", &self.cells, nums)?;
+        enc.begin_instance("highCells", 0, self.cells.clone());
+        for cell in cells {
+            enc.add_expr(nums.at(cell).ge(self.minimum));
+        }
+        Ok(())
+    }
+}
+
+fn registry() -> RuleRegistry {
+    let mut registry = RuleRegistry::default();
+    registry.register::<HighCells>("highCells");
+    registry
+}
+
+/// A 4x4 sudoku with two solutions, which differ in the four empty cells:
+///
+/// ```text
+/// 1 2 3 4      3 2 1 4
+/// 3 4 1 2      1 4 3 2
+/// 2 1 4 3      2 1 4 3
+/// 4 3 2 1      4 3 2 1
+/// ```
+fn puzzle_json(high_cells: &str) -> String {
+    format!(
+        r#"{{
+            "version": 2,
+            "size": 4,
+            "givenNumbers": {{
+                "numbers": [
+                    [null, 2, null, 4],
+                    [null, 4, null, 2],
+                    [2, 1, 4, 3],
+                    [4, 3, 2, 1]
+                ]
+            }},
+            "blocks": {{
+                "horizontalBorder": [
+                    [false, false, false, false],
+                    [true, true, true, true],
+                    [false, false, false, false]
+                ],
+                "verticalBorder": [
+                    [false, true, false],
+                    [false, true, false],
+                    [false, true, false],
+                    [false, true, false]
+                ]
+            }},
+            "highCells": {}
+        }}"#,
+        high_cells
+    )
+}
+
+#[test]
+fn custom_rule_is_parsed_and_validated() {
+    let puzzle = registry()
+        .parse_puzzle(puzzle_json(r#"{ "cells": [{ "x": 0, "y": 4 }], "minimum": 3 }"#).as_bytes())
+        .unwrap();
+    assert_eq!(puzzle.custom_rules.len(), 1);
+    assert!(puzzle.rules().iter().any(|rule| rule.key() == "highCells"));
+
+    let diagnostics = puzzle.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, ErrorCode::OutOfRange);
+    assert_eq!(diagnostics[0].rule, "highCells");
+}
+
+#[test]
+fn unregistered_rule_is_ignored() {
+    let puzzle = RuleRegistry::default()
+        .parse_puzzle(puzzle_json(r#"{ "cells": [], "minimum": 3 }"#).as_bytes())
+        .unwrap();
+    assert!(puzzle.custom_rules.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "the built-in rule killer cannot be registered")]
+fn built_in_rule_cannot_be_registered() {
+    RuleRegistry::default().register::<HighCells>("killer");
+}
+
+#[test]
+fn custom_rule_is_solved_with() {
+    let puzzle = registry()
+        .parse_puzzle(puzzle_json(r#"{ "cells": [{ "x": 0, "y": 0 }], "minimum": 3 }"#).as_bytes())
+        .unwrap();
+
+    let SolveResult::Satisfiable(facts) =
+        irrefutable_facts(&puzzle, SolverConfig::default()).unwrap()
+    else {
+        panic!("the puzzle has a solution");
+    };
+    assert_eq!(
+        facts.decided_nums,
+        [[3, 2, 1, 4], [1, 4, 3, 2], [2, 1, 4, 3], [4, 3, 2, 1]]
+            .map(|row| row.map(Some).to_vec())
+            .to_vec()
+    );
+}
@@ -0,0 +1,172 @@
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::puzzle::Puzzle;
+use sudoku_variants_solver::solver::{
+    irrefutable_facts, AdjacencyEncoding, OrderEncoding, SolverConfig, SumEncoding,
+};
+
+/// A 4x4 sudoku with 2x2 blocks and the rules of `rules` added.
+fn sudoku_4x4(numbers: Value, rules: Value) -> Puzzle {
+    let mut puzzle = json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": { "numbers": numbers },
+        "blocks": {
+            "horizontalBorder": [
+                [false, false, false, false],
+                [true, true, true, true],
+                [false, false, false, false]
+            ],
+            "verticalBorder": vec![[false, true, false]; 4]
+        }
+    });
+    for (key, data) in rules.as_object().unwrap() {
+        puzzle[key] = data.clone();
+    }
+    serde_json::from_value(puzzle).unwrap()
+}
+
+fn empty() -> Value {
+    json!(vec![[None::<i32>; 4]; 4])
+}
+
+/// Puzzles using every constraint family with a choice of encoding.
+fn examples() -> Vec<(&'static str, Puzzle)> {
+    vec![
+        (
+            "killer",
+            sudoku_4x4(
+                empty(),
+                json!({
+                    "killer": {
+                        "regions": [
+                            { "cells": [{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }], "extraValue": 3 },
+                            { "cells": [{ "x": 2, "y": 0 }, { "x": 2, "y": 1 }], "extraValue": 5 },
+                            { "cells": [{ "x": 1, "y": 2 }, { "x": 1, "y": 3 }], "extraValue": null }
+                        ],
+                        "distinct": true
+                    }
+                }),
+            ),
+        ),
+        (
+            "xSums",
+            sudoku_4x4(
+                empty(),
+                json!({
+                    "xSums": {
+                        "up": [3, null, null, null],
+                        "down": [null, null, null, null],
+                        "left": [10, null, null, null],
+                        "right": [null, null, 1, null]
+                    }
+                }),
+            ),
+        ),
+        (
+            "thermo",
+            sudoku_4x4(
+                empty(),
+                json!({
+                    "thermo": {
+                        "thermos": [[{ "x": 0, "y": 0 }, { "x": 1, "y": 1 }, { "x": 2, "y": 1 }]]
+                    }
+                }),
+            ),
+        ),
+        (
+            "skyscrapers",
+            sudoku_4x4(
+                empty(),
+                json!({
+                    "skyscrapers": {
+                        "up": [4, null, null, null],
+                        "down": [null, null, 2, null],
+                        "left": [null, 3, null, null],
+                        "right": [null, null, null, null]
+                    }
+                }),
+            ),
+        ),
+        (
+            "xv",
+            sudoku_4x4(
+                json!([
+                    [null, null, null, null],
+                    [1, null, null, null],
+                    [null, null, null, null],
+                    [null, null, null, null]
+                ]),
+                json!({
+                    "xv": {
+                        "horizontalBorder": [[0, 0, 0, 0], [0, 2, 0, 0], [0, 0, 0, 0]],
+                        "verticalBorder": [[2, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0]],
+                        "allShown": false
+                    }
+                }),
+            ),
+        ),
+        (
+            "consecutive",
+            sudoku_4x4(
+                json!([
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [null, 1, null, null]
+                ]),
+                json!({
+                    "consecutive": {
+                        "horizontalBorder": [
+                            [false, false, false, false],
+                            [false, false, false, false],
+                            [false, true, false, false]
+                        ],
+                        "verticalBorder": vec![[false, false, false]; 4],
+                        "allShown": true
+                    }
+                }),
+            ),
+        ),
+        (
+            "nonConsecutive",
+            sudoku_4x4(
+                json!([
+                    [null, null, null, null],
+                    [null, 1, null, null],
+                    [null, null, null, null],
+                    [null, null, null, null]
+                ]),
+                json!({ "nonConsecutive": {} }),
+            ),
+        ),
+    ]
+}
+
+#[test]
+fn every_encoding_gives_the_default_facts() {
+    for (name, puzzle) in examples() {
+        let expected = irrefutable_facts(&puzzle, SolverConfig::default()).unwrap();
+        for sum in [SumEncoding::Linear, SumEncoding::Combination] {
+            for order in [OrderEncoding::Order, OrderEncoding::Direct] {
+                for adjacency in [AdjacencyEncoding::Arithmetic, AdjacencyEncoding::Table] {
+                    let config = SolverConfig {
+                        sum_encoding: Some(sum),
+                        order_encoding: Some(order),
+                        adjacency_encoding: Some(adjacency),
+                        ..SolverConfig::default()
+                    };
+                    assert_eq!(
+                        irrefutable_facts(&puzzle, config).unwrap(),
+                        expected,
+                        "{} with {:?}, {:?} and {:?}",
+                        name,
+                        sum,
+                        order,
+                        adjacency
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use serde_json::json;
+
+use sudoku_variants_solver::error::ErrorCode;
+use sudoku_variants_solver::explain::explain_elimination;
+use sudoku_variants_solver::puzzle::{Pos, Puzzle};
+use sudoku_variants_solver::solver::SolverConfig;
+
+#[test]
+fn digit_outside_the_digit_set_is_rejected() {
+    let puzzle: Puzzle = serde_json::from_value(json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": { "numbers": [[1, null, null, null]] }
+    }))
+    .unwrap();
+    for digit in [0, 5] {
+        let err = explain_elimination(&puzzle, SolverConfig::default(), Pos { x: 1, y: 0 }, digit)
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::OutOfRange);
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::hint::{find_break_ins, next_hint, BreakIns, HintKind};
+use sudoku_variants_solver::progress::PlayerState;
+use sudoku_variants_solver::puzzle::{Pos, Puzzle};
+use sudoku_variants_solver::solver::SolverConfig;
+
+/// A 4x4 sudoku with 2x2 blocks and a thermometer from (0, 0) to (1, 0).
+fn thermo_4x4(numbers: Value) -> Puzzle {
+    serde_json::from_value(json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": { "numbers": numbers },
+        "blocks": {
+            "horizontalBorder": [
+                [false, false, false, false],
+                [true, true, true, true],
+                [false, false, false, false]
+            ],
+            "verticalBorder": vec![[false, true, false]; 4]
+        },
+        "thermo": { "thermos": [[{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }]] }
+    }))
+    .unwrap()
+}
+
+/// A puzzle with a placement at (0, 3) and an elimination at (0, 0) which only the thermometer
+/// justifies.
+fn puzzle() -> Puzzle {
+    thermo_4x4(json!([
+        [null, null, null, null],
+        [null, null, null, null],
+        [null, null, null, null],
+        [null, 1, 2, 3]
+    ]))
+}
+
+fn reason_of(hint: &sudoku_variants_solver::hint::Hint) -> Vec<(&str, usize)> {
+    hint.reason
+        .iter()
+        .map(|instance| (instance.rule.as_str(), instance.index))
+        .collect()
+}
+
+#[test]
+fn hint_has_the_smallest_reason() {
+    let hint = next_hint(&puzzle(), &PlayerState::default(), SolverConfig::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(hint.kind, HintKind::Elimination);
+    assert_eq!(hint.cell, Pos { x: 0, y: 0 });
+    assert_eq!(hint.digit, 4);
+    assert_eq!(reason_of(&hint), vec![("thermo", 0)]);
+}
+
+#[test]
+fn hint_skips_eliminations_missing_from_pencil_marks() {
+    let state: PlayerState = serde_json::from_value(json!({
+        "pencilMarks": [
+            [[1, 2, 3], null, null, null],
+            [null, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null]
+        ]
+    }))
+    .unwrap();
+    let hint = next_hint(&puzzle(), &state, SolverConfig::default())
+        .unwrap()
+        .unwrap();
+    assert_ne!((hint.cell, hint.digit), (Pos { x: 0, y: 0 }, 4));
+}
+
+#[test]
+fn solved_puzzle_has_no_hint() {
+    let state: PlayerState = serde_json::from_value(json!({
+        "numbers": [
+            [1, 2, 3, 4],
+            [3, 4, 1, 2],
+            [2, 3, 4, 1],
+            [4, null, null, null]
+        ]
+    }))
+    .unwrap();
+    let hint = next_hint(&puzzle(), &state, SolverConfig::default()).unwrap();
+    assert_eq!(hint, None);
+}
+
+#[test]
+fn time_limit_leaves_the_hint_unchanged() {
+    let config = SolverConfig {
+        time_limit: Some(Duration::from_secs(60)),
+        ..SolverConfig::default()
+    };
+    let limited = next_hint(&puzzle(), &PlayerState::default(), config).unwrap();
+    let unlimited = next_hint(&puzzle(), &PlayerState::default(), SolverConfig::default());
+    assert_eq!(limited, unlimited.unwrap());
+}
+
+#[test]
+fn break_ins_are_ranked_by_reason_size() {
+    let break_ins = find_break_ins(&puzzle(), SolverConfig::default())
+        .unwrap()
+        .unwrap();
+    assert!(!break_ins.timed_out);
+
+    let sizes = break_ins
+        .deductions
+        .iter()
+        .map(|hint| hint.reason.len())
+        .collect::<Vec<_>>();
+    assert!(sizes.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let first = &break_ins.deductions[0];
+    assert_eq!(
+        (first.kind, first.cell, first.digit),
+        (HintKind::Elimination, Pos { x: 0, y: 0 }, 4)
+    );
+    assert_eq!(reason_of(first), vec![("thermo", 0)]);
+    assert!(break_ins.deductions.iter().any(|hint| {
+        (hint.kind, hint.cell, hint.digit) == (HintKind::Placement, Pos { x: 0, y: 3 }, 4)
+    }));
+}
+
+#[test]
+fn unsolvable_puzzle_has_no_break_ins() {
+    let puzzle = thermo_4x4(json!([
+        [4, null, null, null],
+        [null, null, null, null],
+        [null, null, null, null],
+        [null, null, null, null]
+    ]));
+    assert_eq!(
+        find_break_ins(&puzzle, SolverConfig::default()).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn exhausted_time_limit_stops_the_break_in_search() {
+    let config = SolverConfig {
+        time_limit: Some(Duration::ZERO),
+        ..SolverConfig::default()
+    };
+    assert_eq!(
+        find_break_ins(&puzzle(), config).unwrap(),
+        Some(BreakIns {
+            deductions: vec![],
+            timed_out: true,
+        })
+    );
+}
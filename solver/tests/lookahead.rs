@@ -0,0 +1,53 @@
+use sudoku_variants_solver::error::ErrorCode;
+use sudoku_variants_solver::lookahead::propagation_lookahead_depth;
+use sudoku_variants_solver::puzzle::{AntiKnight, Blocks, GivenNumbers, Puzzle};
+
+/// A 9x9 sudoku given row by row, with `0` for empty cells.
+fn classic(givens: &str) -> Puzzle {
+    let numbers = givens
+        .as_bytes()
+        .chunks(9)
+        .map(|row| {
+            row.iter()
+                .map(|&c| Some((c - b'0') as i32).filter(|&d| d > 0))
+                .collect()
+        })
+        .collect();
+    Puzzle {
+        size: 9,
+        given_numbers: GivenNumbers { numbers },
+        blocks: Some(Blocks {
+            horizontal: (0..8).map(|y| vec![y % 3 == 2; 9]).collect(),
+            vertical: vec![(0..8).map(|x| x % 3 == 2).collect(); 9],
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn propagation_solves_an_easy_puzzle() {
+    let puzzle = classic(
+        "030050040008010500460000012070502080000603000040109030250000098001020600080060020",
+    );
+    assert_eq!(propagation_lookahead_depth(&puzzle, 2).unwrap(), Some(0));
+}
+
+#[test]
+fn single_lookahead_is_needed() {
+    let puzzle = classic(
+        "100000569492056108056109240009640801064010000218035604040500016905061402621000005",
+    );
+    assert_eq!(propagation_lookahead_depth(&puzzle, 0).unwrap(), None);
+    assert_eq!(propagation_lookahead_depth(&puzzle, 2).unwrap(), Some(1));
+}
+
+#[test]
+fn unsupported_rules_are_rejected() {
+    let mut puzzle = classic(
+        "030050040008010500460000012070502080000603000040109030250000098001020600080060020",
+    );
+    puzzle.anti_knight = Some(AntiKnight {});
+    let err = propagation_lookahead_depth(&puzzle, 2).unwrap_err();
+    assert_eq!(err.code, ErrorCode::UnsupportedValue);
+    assert_eq!(err.rule.as_deref(), Some("antiKnight"));
+}
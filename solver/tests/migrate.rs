@@ -0,0 +1,80 @@
+use serde_json::json;
+
+use sudoku_variants_solver::migrate::{migrate, CURRENT_VERSION};
+use sudoku_variants_solver::puzzle::Puzzle;
+use sudoku_variants_solver::rule::RuleRegistry;
+
+#[test]
+fn unversioned_puzzle_is_upgraded() {
+    let mut puzzle = json!({
+        "size": 4,
+        "givenNumbers": { "numbers": [[1, null, null, null]] }
+    });
+    migrate(&mut puzzle).unwrap();
+    assert_eq!(puzzle["version"], json!(CURRENT_VERSION));
+    assert_eq!(puzzle["givenNumbers"]["numbers"][0][0], json!(1));
+}
+
+#[test]
+fn parsed_puzzle_has_current_version() {
+    let puzzle = RuleRegistry::default()
+        .parse_puzzle(br#"{"size": 4, "givenNumbers": {"numbers": []}}"#)
+        .unwrap();
+    assert_eq!(puzzle.version, CURRENT_VERSION);
+}
+
+#[test]
+fn newer_version_is_rejected() {
+    let mut puzzle = json!({
+        "version": CURRENT_VERSION + 1,
+        "size": 4,
+        "givenNumbers": { "numbers": [] }
+    });
+    assert!(migrate(&mut puzzle).is_err());
+}
+
+#[test]
+fn forbidden_candidates_are_indexed_by_value_from_v2() {
+    let mut puzzle = json!({
+        "version": 1,
+        "size": 0,
+        "height": 2,
+        "width": 2,
+        "digits": [4, 2],
+        "givenNumbers": { "numbers": [] },
+        "forbiddenCandidates": {
+            "isForbidden": [[[true, false], [false, false]], [[false, true], [true, true]]]
+        }
+    });
+    migrate(&mut puzzle).unwrap();
+    assert_eq!(
+        puzzle["forbiddenCandidates"]["isForbidden"],
+        json!([
+            [
+                [false, false, true, false, false],
+                [false, false, false, false, false]
+            ],
+            [
+                [false, false, false, false, true],
+                [false, false, true, false, true]
+            ]
+        ])
+    );
+}
+
+#[test]
+fn deserializing_a_puzzle_migrates_it() {
+    let puzzle: Puzzle = serde_json::from_value(json!({
+        "version": 1,
+        "size": 2,
+        "givenNumbers": { "numbers": [[null, null], [null, null]] },
+        "forbiddenCandidates": {
+            "isForbidden": [[[true, false], [false, false]], [[false, false], [false, true]]]
+        }
+    }))
+    .unwrap();
+    assert_eq!(puzzle.version, CURRENT_VERSION);
+    let is_forbidden = &puzzle.forbidden_candidates.unwrap().is_forbidden;
+    assert_eq!(is_forbidden[0][0], vec![false, true, false]);
+    assert_eq!(is_forbidden[1][1], vec![false, false, true]);
+}
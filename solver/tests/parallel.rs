@@ -0,0 +1,64 @@
+#![cfg(feature = "parallel")]
+
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::parallel::irrefutable_facts_parallel;
+use sudoku_variants_solver::puzzle::Puzzle;
+use sudoku_variants_solver::solver::{irrefutable_facts, SolverConfig};
+
+/// A 4x4 sudoku with 2x2 blocks.
+fn sudoku_4x4(numbers: Value) -> Puzzle {
+    serde_json::from_value(json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": { "numbers": numbers },
+        "blocks": {
+            "horizontalBorder": [
+                [false, false, false, false],
+                [true, true, true, true],
+                [false, false, false, false]
+            ],
+            "verticalBorder": vec![[false, true, false]; 4]
+        }
+    }))
+    .unwrap()
+}
+
+fn assert_same_as_serial(puzzle: &Puzzle) {
+    let serial = irrefutable_facts(puzzle, SolverConfig::default()).unwrap();
+    for num_threads in [1, 2, 4] {
+        let parallel =
+            irrefutable_facts_parallel(puzzle, SolverConfig::default(), num_threads).unwrap();
+        assert_eq!(parallel, serial, "{} threads", num_threads);
+    }
+}
+
+#[test]
+fn unique_puzzle_matches_serial() {
+    assert_same_as_serial(&sudoku_4x4(json!([
+        [1, 2, null, 4],
+        [null, 4, 1, null],
+        [null, 1, 4, null],
+        [4, null, null, 1]
+    ])));
+}
+
+#[test]
+fn ambiguous_puzzle_matches_serial() {
+    assert_same_as_serial(&sudoku_4x4(json!([
+        [null, null, null, null],
+        [null, null, 1, null],
+        [null, null, null, null],
+        [null, 3, null, null]
+    ])));
+}
+
+#[test]
+fn unsolvable_puzzle_matches_serial() {
+    assert_same_as_serial(&sudoku_4x4(json!([
+        [1, null, null, 1],
+        [null, null, null, null],
+        [null, null, null, null],
+        [null, null, null, null]
+    ])));
+}
@@ -0,0 +1,118 @@
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::progress::{check_progress, PlayerState, ProgressReport};
+use sudoku_variants_solver::puzzle::{Candidate, Pos, Puzzle};
+use sudoku_variants_solver::solver::SolverConfig;
+
+/// Checks `answer`, in the format of the editor's answer rule, against a 4x4 sudoku whose
+/// solution is
+///
+/// ```text
+/// 1 2 3 4
+/// 3 4 1 2
+/// 2 1 4 3
+/// 4 3 2 1
+/// ```
+fn check(answer: Value) -> ProgressReport {
+    let puzzle: Puzzle = serde_json::from_value(json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": {
+            "numbers": [
+                [1, 2, null, 4],
+                [null, 4, 1, null],
+                [null, 1, 4, null],
+                [4, null, null, 1]
+            ]
+        },
+        "blocks": {
+            "horizontalBorder": [
+                [false, false, false, false],
+                [true, true, true, true],
+                [false, false, false, false]
+            ],
+            "verticalBorder": vec![[false, true, false]; 4]
+        },
+        "answer": answer
+    }))
+    .unwrap();
+    let state = PlayerState::from_puzzle(&puzzle).unwrap();
+    check_progress(&puzzle, &state, SolverConfig::default()).unwrap()
+}
+
+fn candidate(x: usize, y: usize, digit: i32) -> Candidate {
+    Candidate {
+        cell: Pos { x, y },
+        digit,
+    }
+}
+
+#[test]
+fn consistent_state_is_solvable() {
+    let report = check(json!({
+        "numbers": [
+            [null, null, 3, null],
+            [3, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null]
+        ],
+        "pencilMarks": [
+            [null, null, null, null],
+            [null, null, null, [2, 3]],
+            [[2, 3], null, null, null],
+            [null, null, null, null]
+        ]
+    }));
+    assert_eq!(
+        report,
+        ProgressReport {
+            wrong_entries: vec![],
+            wrong_eliminations: vec![],
+            solvable: true,
+            timed_out: false,
+        }
+    );
+}
+
+#[test]
+fn wrong_digit_is_reported() {
+    let report = check(json!({
+        "numbers": [
+            [null, null, 3, null],
+            [2, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null]
+        ]
+    }));
+    assert_eq!(report.wrong_entries, vec![candidate(0, 1, 2)]);
+    assert!(!report.solvable);
+}
+
+#[test]
+fn digit_overwriting_a_given_is_wrong() {
+    let report = check(json!({
+        "numbers": [
+            [3, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null]
+        ]
+    }));
+    assert_eq!(report.wrong_entries, vec![candidate(0, 0, 3)]);
+    assert!(!report.solvable);
+}
+
+#[test]
+fn wrong_elimination_is_reported() {
+    let report = check(json!({
+        "pencilMarks": [
+            [null, null, [1, 2], null],
+            [null, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null]
+        ]
+    }));
+    assert_eq!(report.wrong_entries, vec![]);
+    assert_eq!(report.wrong_eliminations, vec![candidate(2, 0, 3)]);
+    assert!(report.solvable);
+}
@@ -0,0 +1,251 @@
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::puzzle::Puzzle;
+
+/// Checks that `puzzle_json` survives `deserialize → serialize → deserialize` unchanged.
+///
+/// `Puzzle` has no `PartialEq` (custom rules are trait objects), so the puzzles are compared
+/// through their serialized form, which must also equal the input.
+fn assert_round_trip(puzzle_json: Value) {
+    let puzzle: Puzzle = serde_json::from_value(puzzle_json.clone()).unwrap();
+    let serialized = serde_json::to_value(&puzzle).unwrap();
+    assert_eq!(serialized, puzzle_json);
+
+    let reparsed: Puzzle = serde_json::from_value(serialized.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&reparsed).unwrap(), serialized);
+}
+
+fn with_rule(key: &str, data: Value) -> Value {
+    let mut puzzle = json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": {
+            "numbers": [
+                [1, null, null, null],
+                [null, null, 3, null],
+                [null, 4, null, null],
+                [null, null, null, 2]
+            ]
+        }
+    });
+    puzzle[key] = data;
+    puzzle
+}
+
+#[test]
+fn round_trip_given_numbers_only() {
+    assert_round_trip(json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": {
+            "numbers": [
+                [null, null, null, null],
+                [null, null, null, null],
+                [null, null, null, null],
+                [null, null, null, null]
+            ]
+        }
+    }));
+}
+
+#[test]
+fn round_trip_board_shape() {
+    assert_round_trip(json!({
+        "version": 2,
+        "size": 0,
+        "height": 2,
+        "width": 3,
+        "digits": [1, 2, 3],
+        "houseDigits": [1, 2, 3],
+        "givenNumbers": { "numbers": [[null, 2, null], [null, null, 1]] }
+    }));
+}
+
+#[test]
+fn round_trip_blocks() {
+    assert_round_trip(with_rule(
+        "blocks",
+        json!({
+            "horizontalBorder": [
+                [false, false, false, false],
+                [true, true, true, true],
+                [false, false, false, false]
+            ],
+            "verticalBorder": [
+                [false, true, false],
+                [false, true, false],
+                [false, true, false],
+                [false, true, false]
+            ]
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_odd_even() {
+    assert_round_trip(with_rule(
+        "oddEven",
+        json!({ "cellKind": [[0, 1, 2, 0], [0, 0, 0, 0], [2, 0, 0, 1], [0, 0, 0, 0]] }),
+    ));
+}
+
+#[test]
+fn round_trip_non_consecutive() {
+    assert_round_trip(with_rule("nonConsecutive", json!({})));
+}
+
+#[test]
+fn round_trip_xv() {
+    assert_round_trip(with_rule(
+        "xv",
+        json!({
+            "horizontalBorder": [[0, 1, 0, 0], [0, 0, 0, 0], [2, 0, 0, 0]],
+            "verticalBorder": [[0, 0, 2], [0, 0, 0], [1, 0, 0], [0, 0, 0]],
+            "allShown": true
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_diagonal() {
+    assert_round_trip(with_rule(
+        "diagonal",
+        json!({ "mainDiagonal": true, "antiDiagonal": false }),
+    ));
+}
+
+#[test]
+fn round_trip_arrow() {
+    assert_round_trip(with_rule(
+        "arrow",
+        json!({
+            "arrows": [[{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }, { "x": 2, "y": 1 }]]
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_thermo() {
+    assert_round_trip(with_rule(
+        "thermo",
+        json!({
+            "thermos": [
+                [{ "x": 0, "y": 3 }, { "x": 1, "y": 2 }],
+                [{ "x": 3, "y": 0 }, { "x": 3, "y": 1 }, { "x": 3, "y": 2 }]
+            ]
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_killer() {
+    assert_round_trip(with_rule(
+        "killer",
+        json!({
+            "regions": [
+                { "cells": [{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }], "extraValue": 5 },
+                { "cells": [{ "x": 2, "y": 2 }, { "x": 2, "y": 3 }], "extraValue": null }
+            ],
+            "distinct": true
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_consecutive() {
+    assert_round_trip(with_rule(
+        "consecutive",
+        json!({
+            "horizontalBorder": [
+                [true, false, false, false],
+                [false, false, false, false],
+                [false, false, false, false]
+            ],
+            "verticalBorder": [
+                [false, false, false],
+                [false, true, false],
+                [false, false, false],
+                [false, false, false]
+            ],
+            "allShown": false
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_skyscrapers() {
+    assert_round_trip(with_rule(
+        "skyscrapers",
+        json!({
+            "up": [1, null, null, 2],
+            "down": [null, null, null, null],
+            "left": [null, 3, null, null],
+            "right": [null, null, null, 4]
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_x_sums() {
+    assert_round_trip(with_rule(
+        "xSums",
+        json!({
+            "up": [null, 7, null, null],
+            "down": [null, null, null, null],
+            "left": [10, null, null, null],
+            "right": [null, null, 3, null]
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_extra_regions() {
+    assert_round_trip(with_rule(
+        "extraRegions",
+        json!({
+            "regions": [
+                { "cells": [{ "x": 0, "y": 0 }, { "x": 1, "y": 1 }] },
+                { "cells": [{ "x": 2, "y": 0 }, { "x": 3, "y": 1 }], "digits": [1, 2] }
+            ]
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_palindrome() {
+    assert_round_trip(with_rule(
+        "palindrome",
+        json!({
+            "palindromes": [[{ "x": 0, "y": 1 }, { "x": 1, "y": 1 }, { "x": 2, "y": 1 }]]
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_forbidden_candidates() {
+    let mut is_forbidden = vec![vec![vec![false; 5]; 4]; 4];
+    is_forbidden[1][2][1] = true;
+    is_forbidden[3][0][4] = true;
+    assert_round_trip(with_rule(
+        "forbiddenCandidates",
+        json!({ "isForbidden": is_forbidden }),
+    ));
+}
+
+#[test]
+fn round_trip_anti_knight() {
+    assert_round_trip(with_rule("antiKnight", json!({})));
+}
+
+#[test]
+fn round_trip_no_touch() {
+    assert_round_trip(with_rule("noTouch", json!({})));
+}
+
+#[test]
+fn round_trip_keeps_unknown_keys() {
+    assert_round_trip(with_rule(
+        "answer",
+        json!({ "numbers": [[1, 2, 3, 4], [3, 4, 1, 2], [2, 1, 4, 3], [4, 3, 2, 1]] }),
+    ));
+}
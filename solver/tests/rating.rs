@@ -0,0 +1,42 @@
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::error::ErrorCode;
+use sudoku_variants_solver::logic::unsupported_rules;
+use sudoku_variants_solver::puzzle::Puzzle;
+use sudoku_variants_solver::rating::rate;
+use sudoku_variants_solver::rule::RuleRegistry;
+use sudoku_variants_solver::solver::SolverConfig;
+
+fn puzzle_with(key: &str, data: Value) -> Puzzle {
+    let mut puzzle = json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": { "numbers": [[1, null, null, null]] }
+    });
+    puzzle[key] = data;
+    RuleRegistry::default()
+        .parse_puzzle(puzzle.to_string().as_bytes())
+        .unwrap()
+}
+
+#[test]
+fn supported_rules_are_not_reported() {
+    let puzzle = puzzle_with(
+        "killer",
+        json!({
+            "regions": [{ "cells": [{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }], "extraValue": 3 }],
+            "distinct": true
+        }),
+    );
+    assert!(unsupported_rules(&puzzle).is_empty());
+}
+
+#[test]
+fn rating_rejects_unsupported_rules() {
+    let puzzle = puzzle_with("nonConsecutive", json!({}));
+    assert_eq!(unsupported_rules(&puzzle), vec!["nonConsecutive"]);
+
+    let err = rate(&puzzle, SolverConfig::default()).unwrap_err();
+    assert_eq!(err.code, ErrorCode::UnsupportedValue);
+    assert_eq!(err.rule.as_deref(), Some("nonConsecutive"));
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "schema")]
+
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use sudoku_variants_solver::puzzle::Puzzle;
+
+/// `puzzle.schema.json` is generated by `cargo run --features schema --bin schema`; this fails
+/// when it has not been regenerated after a change to `Puzzle`.
+#[test]
+fn schema_file_is_up_to_date() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("puzzle.schema.json");
+    let file = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("cannot read {}: {}", path.display(), err));
+    let expected: Value = serde_json::from_str(&file).unwrap();
+    let actual = serde_json::to_value(schemars::schema_for!(Puzzle)).unwrap();
+    assert_eq!(
+        actual, expected,
+        "puzzle.schema.json is stale (run `cargo run --features schema --bin schema > puzzle.schema.json`)"
+    );
+}
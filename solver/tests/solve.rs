@@ -0,0 +1,243 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::error::ErrorCode;
+use sudoku_variants_solver::puzzle::{Pos, Puzzle, XV};
+use sudoku_variants_solver::solver::{
+    count_solutions, find_ambiguity, irrefutable_facts, solutions, SolutionCount, SolveResult,
+    SolverConfig,
+};
+
+/// A 4x4 sudoku with 2x2 blocks.
+fn sudoku_4x4(numbers: Value) -> Puzzle {
+    serde_json::from_value(json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": { "numbers": numbers },
+        "blocks": {
+            "horizontalBorder": [
+                [false, false, false, false],
+                [true, true, true, true],
+                [false, false, false, false]
+            ],
+            "verticalBorder": vec![[false, true, false]; 4]
+        }
+    }))
+    .unwrap()
+}
+
+fn unique_4x4() -> Puzzle {
+    sudoku_4x4(json!([
+        [1, 2, null, 4],
+        [null, 4, 1, null],
+        [null, 1, 4, null],
+        [4, null, null, 1]
+    ]))
+}
+
+/// The solution of `unique_4x4` without the four cells holding 1 and 2 in its first two
+/// columns, which can be swapped.
+fn ambiguous_4x4() -> Puzzle {
+    sudoku_4x4(json!([
+        [null, null, 3, 4],
+        [3, 4, 1, 2],
+        [null, null, 4, 3],
+        [4, 3, 2, 1]
+    ]))
+}
+
+fn unsolvable_4x4() -> Puzzle {
+    sudoku_4x4(json!([
+        [1, null, null, 1],
+        [null, null, null, null],
+        [null, null, null, null],
+        [null, null, null, null]
+    ]))
+}
+
+/// Asserts that the search stopped before finishing any row, so that every digit is still
+/// reported as possible everywhere.
+fn assert_nothing_finished(result: SolveResult) {
+    let SolveResult::Satisfiable(facts) = result else {
+        panic!("a partial result is reported as satisfiable");
+    };
+    assert!(facts.timed_out);
+    for cell in facts.candidates.iter().flatten() {
+        assert_eq!(cell, &vec![false, true, true, true, true]);
+    }
+}
+
+#[test]
+fn exhausted_time_limit_reports_partial_facts() {
+    let config = SolverConfig {
+        time_limit: Some(Duration::ZERO),
+        ..SolverConfig::default()
+    };
+    assert_nothing_finished(irrefutable_facts(&unique_4x4(), config).unwrap());
+}
+
+#[test]
+fn cancelled_search_reports_partial_facts() {
+    let config = SolverConfig {
+        cancel: Some(Arc::new(AtomicBool::new(true))),
+        ..SolverConfig::default()
+    };
+    assert_nothing_finished(irrefutable_facts(&unique_4x4(), config).unwrap());
+}
+
+#[test]
+fn time_limit_leaves_complete_facts_unchanged() {
+    let config = SolverConfig {
+        time_limit: Some(Duration::from_secs(60)),
+        ..SolverConfig::default()
+    };
+    let limited = irrefutable_facts(&unique_4x4(), config).unwrap();
+    let unlimited = irrefutable_facts(&unique_4x4(), SolverConfig::default()).unwrap();
+    assert_eq!(limited, unlimited);
+    let SolveResult::Satisfiable(facts) = limited else {
+        panic!("the puzzle has a solution");
+    };
+    assert!(!facts.timed_out);
+}
+
+#[test]
+fn minimized_conflict_is_not_timed_out() {
+    let SolveResult::Unsatisfiable {
+        conflict,
+        timed_out,
+    } = irrefutable_facts(&unsolvable_4x4(), SolverConfig::default()).unwrap()
+    else {
+        panic!("the puzzle has no solution");
+    };
+    assert!(!timed_out);
+    let conflict = conflict
+        .iter()
+        .map(|instance| (instance.rule.as_str(), instance.index))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        conflict,
+        vec![("row", 0), ("givenNumbers", 0), ("givenNumbers", 3)]
+    );
+}
+
+fn count(puzzle: &Puzzle, limit: usize) -> SolutionCount {
+    count_solutions(puzzle, limit, SolverConfig::default()).unwrap()
+}
+
+#[test]
+fn unique_puzzle_has_one_solution() {
+    assert_eq!(
+        count(&unique_4x4(), 5),
+        SolutionCount {
+            count: 1,
+            limit_reached: false,
+        }
+    );
+}
+
+#[test]
+fn ambiguous_puzzle_has_two_solutions() {
+    assert_eq!(
+        count(&ambiguous_4x4(), 5),
+        SolutionCount {
+            count: 2,
+            limit_reached: false,
+        }
+    );
+}
+
+#[test]
+fn unsolvable_puzzle_has_no_solution() {
+    assert_eq!(
+        count(&unsolvable_4x4(), 5),
+        SolutionCount {
+            count: 0,
+            limit_reached: false,
+        }
+    );
+}
+
+#[test]
+fn counting_stops_at_the_limit() {
+    // The empty 4x4 sudoku has 288 solutions.
+    let empty = sudoku_4x4(json!(vec![[None::<i32>; 4]; 4]));
+    assert_eq!(
+        count(&empty, 10),
+        SolutionCount {
+            count: 10,
+            limit_reached: true,
+        }
+    );
+}
+
+#[test]
+fn zero_limit_is_rejected() {
+    let err = count_solutions(&unique_4x4(), 0, SolverConfig::default()).unwrap_err();
+    assert_eq!(err.code, ErrorCode::OutOfRange);
+}
+
+#[test]
+fn solutions_are_distinct_and_exhaustive() {
+    let found = solutions(&ambiguous_4x4(), SolverConfig::default())
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert_eq!(found.len(), 2);
+    assert_ne!(found[0], found[1]);
+}
+
+#[test]
+fn ambiguity_shows_where_two_solutions_differ() {
+    let ambiguity = find_ambiguity(&ambiguous_4x4(), SolverConfig::default())
+        .unwrap()
+        .unwrap();
+    let [first, second] = &ambiguity.solutions;
+    assert_ne!(first, second);
+    assert_eq!(
+        ambiguity.differing_cells,
+        vec![
+            Pos { x: 0, y: 0 },
+            Pos { x: 1, y: 0 },
+            Pos { x: 0, y: 2 },
+            Pos { x: 1, y: 2 },
+        ]
+    );
+    for cell in &ambiguity.differing_cells {
+        assert_ne!(first[cell.y][cell.x], second[cell.y][cell.x]);
+    }
+}
+
+#[test]
+fn unique_puzzle_has_no_ambiguity() {
+    assert_eq!(
+        find_ambiguity(&unique_4x4(), SolverConfig::default()).unwrap(),
+        None
+    );
+}
+
+/// `unique_4x4` with XV borders, none of which is marked.
+fn unmarked_xv_4x4(all_shown: bool) -> Puzzle {
+    let mut puzzle = unique_4x4();
+    puzzle.xv = Some(XV {
+        horizontal: vec![vec![0; 4]; 3],
+        vertical: vec![vec![0; 3]; 4],
+        all_shown,
+    });
+    puzzle
+}
+
+#[test]
+fn unmarked_xv_borders_only_constrain_when_all_are_shown() {
+    // The solution has adjacent cells summing to 5 both within a row and within a column.
+    let unconstrained = irrefutable_facts(&unmarked_xv_4x4(false), SolverConfig::default());
+    let plain = irrefutable_facts(&unique_4x4(), SolverConfig::default());
+    assert_eq!(unconstrained.unwrap(), plain.unwrap());
+
+    let constrained = irrefutable_facts(&unmarked_xv_4x4(true), SolverConfig::default());
+    assert!(matches!(
+        constrained.unwrap(),
+        SolveResult::Unsatisfiable { .. }
+    ));
+}
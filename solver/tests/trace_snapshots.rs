@@ -0,0 +1,237 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::rule::RuleRegistry;
+use sudoku_variants_solver::trace::trace_logical_solve;
+
+/// Checks the trace of `puzzle_json` against `tests/snapshots/<name>.json`.
+///
+/// Run with `UPDATE_SNAPSHOTS=1` to write the current traces instead, and review the diff: the
+/// editor replays traces, so their format must only change on purpose.
+fn assert_trace_snapshot(name: &str, puzzle_json: Value) {
+    let puzzle = RuleRegistry::default()
+        .parse_puzzle(puzzle_json.to_string().as_bytes())
+        .unwrap();
+    let trace = trace_logical_solve(&puzzle).unwrap();
+    let actual = serde_json::to_string_pretty(&trace).unwrap() + "\n";
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{}.json", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("cannot read {}: {}", path.display(), err));
+    assert_eq!(
+        actual, expected,
+        "trace of {} differs from its snapshot (run with UPDATE_SNAPSHOTS=1 to update)",
+        name
+    );
+}
+
+fn blocks_4x4() -> Value {
+    json!({
+        "horizontalBorder": [
+            [false, false, false, false],
+            [true, true, true, true],
+            [false, false, false, false]
+        ],
+        "verticalBorder": [
+            [false, true, false],
+            [false, true, false],
+            [false, true, false],
+            [false, true, false]
+        ]
+    })
+}
+
+#[test]
+fn trace_singles() {
+    assert_trace_snapshot(
+        "singles",
+        json!({
+            "version": 2,
+            "size": 4,
+            "givenNumbers": {
+                "numbers": [
+                    [1, null, 3, 4],
+                    [3, 4, null, 2],
+                    [null, 1, 4, 3],
+                    [4, 3, 2, null]
+                ]
+            },
+            "blocks": blocks_4x4()
+        }),
+    );
+}
+
+#[test]
+fn trace_thermo() {
+    assert_trace_snapshot(
+        "thermo",
+        json!({
+            "version": 2,
+            "size": 2,
+            "givenNumbers": { "numbers": [[null, null], [null, null]] },
+            "thermo": { "thermos": [[{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }]] }
+        }),
+    );
+}
+
+#[test]
+fn trace_killer() {
+    assert_trace_snapshot(
+        "killer",
+        json!({
+            "version": 2,
+            "size": 4,
+            "givenNumbers": {
+                "numbers": [
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [null, null, null, 1],
+                    [null, null, null, null]
+                ]
+            },
+            "blocks": blocks_4x4(),
+            "killer": {
+                "regions": [
+                    { "cells": [{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }], "extraValue": 3 },
+                    { "cells": [{ "x": 0, "y": 1 }, { "x": 1, "y": 1 }], "extraValue": 7 },
+                    { "cells": [{ "x": 2, "y": 0 }, { "x": 2, "y": 1 }], "extraValue": 5 }
+                ],
+                "distinct": true
+            }
+        }),
+    );
+}
+
+fn empty_4x4() -> Value {
+    json!({
+        "numbers": [
+            [null, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null],
+            [null, null, null, null]
+        ]
+    })
+}
+
+#[test]
+fn trace_arrow() {
+    assert_trace_snapshot(
+        "arrow",
+        json!({
+            "version": 2,
+            "size": 4,
+            "givenNumbers": empty_4x4(),
+            "blocks": blocks_4x4(),
+            "arrow": { "arrows": [[{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }, { "x": 2, "y": 0 }]] }
+        }),
+    );
+}
+
+#[test]
+fn trace_xv() {
+    assert_trace_snapshot(
+        "xv",
+        json!({
+            "version": 2,
+            "size": 4,
+            "givenNumbers": {
+                "numbers": [
+                    [null, null, null, null],
+                    [1, null, null, null],
+                    [null, null, null, null],
+                    [null, null, null, null]
+                ]
+            },
+            "blocks": blocks_4x4(),
+            "xv": {
+                "horizontalBorder": [[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]],
+                "verticalBorder": [[2, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0]],
+                "allShown": false
+            }
+        }),
+    );
+}
+
+#[test]
+fn trace_consecutive() {
+    assert_trace_snapshot(
+        "consecutive",
+        json!({
+            "version": 2,
+            "size": 4,
+            "givenNumbers": {
+                "numbers": [
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [null, 1, null, null]
+                ]
+            },
+            "blocks": blocks_4x4(),
+            "consecutive": {
+                "horizontalBorder": [
+                    [false, false, false, false],
+                    [false, false, false, false],
+                    [false, true, false, false]
+                ],
+                "verticalBorder": [
+                    [false, false, false],
+                    [false, false, false],
+                    [false, false, false],
+                    [false, false, false]
+                ],
+                "allShown": false
+            }
+        }),
+    );
+}
+
+#[test]
+fn trace_palindrome() {
+    assert_trace_snapshot(
+        "palindrome",
+        json!({
+            "version": 2,
+            "size": 4,
+            "givenNumbers": {
+                "numbers": [
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [2, null, null, null],
+                    [null, null, null, null]
+                ]
+            },
+            "blocks": blocks_4x4(),
+            "palindrome": {
+                "palindromes": [[{ "x": 0, "y": 0 }, { "x": 1, "y": 1 }, { "x": 2, "y": 1 }]]
+            }
+        }),
+    );
+}
+
+#[test]
+fn trace_stuck() {
+    assert_trace_snapshot(
+        "stuck",
+        json!({
+            "version": 2,
+            "size": 4,
+            "givenNumbers": {
+                "numbers": [
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [null, null, null, null],
+                    [null, null, null, null]
+                ]
+            },
+            "blocks": blocks_4x4()
+        }),
+    );
+}
@@ -0,0 +1,115 @@
+use serde_json::{json, Value};
+
+use sudoku_variants_solver::error::ErrorCode;
+use sudoku_variants_solver::puzzle::{Pos, Puzzle};
+use sudoku_variants_solver::validate::{Diagnostic, Severity};
+
+fn puzzle_with(key: &str, data: Value) -> Puzzle {
+    let mut puzzle = json!({
+        "version": 2,
+        "size": 4,
+        "givenNumbers": { "numbers": vec![vec![None::<i32>; 4]; 4] }
+    });
+    puzzle[key] = data;
+    serde_json::from_value(puzzle).unwrap()
+}
+
+fn x_sums_puzzle(up: [Option<i32>; 4]) -> Puzzle {
+    puzzle_with(
+        "xSums",
+        json!({
+            "up": up,
+            "down": [null, null, null, null],
+            "left": [null, null, null, null],
+            "right": [null, null, null, null]
+        }),
+    )
+}
+
+#[test]
+fn x_sums_clues_in_range() {
+    // With digits 1..=4, X-sums range from 1 (a leading 1) to 10 (4 + 3 + 2 + 1).
+    assert!(x_sums_puzzle([Some(1), Some(10), None, Some(7)])
+        .validate()
+        .is_empty());
+}
+
+#[test]
+fn x_sums_clues_out_of_range() {
+    let diagnostics = x_sums_puzzle([Some(0), Some(11), None, None]).validate();
+    assert_eq!(diagnostics.len(), 2);
+    for diagnostic in &diagnostics {
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.rule, "xSums");
+    }
+}
+
+/// Asserts that `puzzle` has exactly one diagnostic, an error with `code` in `rule`.
+fn assert_single_error(puzzle: &Puzzle, code: ErrorCode, rule: &str) -> Diagnostic {
+    let diagnostics = puzzle.validate();
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let diagnostic = diagnostics.into_iter().next().unwrap();
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.code, code);
+    assert_eq!(diagnostic.rule, rule);
+    diagnostic
+}
+
+#[test]
+fn arrow_without_shaft() {
+    let puzzle = puzzle_with("arrow", json!({ "arrows": [[{ "x": 1, "y": 1 }]] }));
+    let diagnostic = assert_single_error(&puzzle, ErrorCode::UnsupportedValue, "arrow");
+    assert_eq!(diagnostic.index, Some(0));
+}
+
+#[test]
+fn thermo_visiting_a_cell_twice() {
+    let puzzle = puzzle_with(
+        "thermo",
+        json!({
+            "thermos": [
+                [{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }],
+                [{ "x": 0, "y": 1 }, { "x": 1, "y": 1 }, { "x": 0, "y": 1 }]
+            ]
+        }),
+    );
+    let diagnostic = assert_single_error(&puzzle, ErrorCode::UnsupportedValue, "thermo");
+    assert_eq!(diagnostic.index, Some(1));
+    assert_eq!(diagnostic.cells, vec![Pos { x: 0, y: 1 }]);
+}
+
+#[test]
+fn killer_cell_outside_the_board() {
+    let puzzle = puzzle_with(
+        "killer",
+        json!({
+            "regions": [{ "cells": [{ "x": 3, "y": 3 }, { "x": 4, "y": 3 }], "extraValue": 5 }],
+            "distinct": true
+        }),
+    );
+    let diagnostic = assert_single_error(&puzzle, ErrorCode::OutOfRange, "killer");
+    assert_eq!(diagnostic.cells, vec![Pos { x: 4, y: 3 }]);
+}
+
+#[test]
+fn forbidden_candidates_of_wrong_length() {
+    // Indexed by value, so each cell needs 5 entries for the digits 1..=4.
+    let mut is_forbidden = vec![vec![vec![false; 5]; 4]; 4];
+    is_forbidden[2][1] = vec![false; 4];
+    let puzzle = puzzle_with(
+        "forbiddenCandidates",
+        json!({ "isForbidden": is_forbidden }),
+    );
+    assert_single_error(&puzzle, ErrorCode::ShapeMismatch, "forbiddenCandidates");
+}
+
+#[test]
+fn given_numbers_outside_the_digit_set() {
+    for value in [0, 5] {
+        let mut numbers = vec![vec![None; 4]; 4];
+        numbers[1][2] = Some(value);
+        let puzzle = puzzle_with("givenNumbers", json!({ "numbers": numbers }));
+        let diagnostic = assert_single_error(&puzzle, ErrorCode::UnsupportedValue, "givenNumbers");
+        assert_eq!(diagnostic.cells, vec![Pos { x: 2, y: 1 }]);
+    }
+}
@@ -127,7 +127,7 @@ const autoSolverItems = (
       const answerNum = answerNumbers[y][x];
       if (answerNum !== null) {
         for (let i = 0; i < size; ++i) {
-          if (answer.candidates[y][x][i] !== (answerNum - 1 === i)) {
+          if (answer.candidates[y][x][i + 1] !== (answerNum - 1 === i)) {
             hasMismatch = true;
           }
         }
@@ -167,7 +167,7 @@ const autoSolverItems = (
         const candidates = answer.candidates[y][x];
         const w = Math.ceil(Math.sqrt(size));
         for (let i = 0; i < size; ++i) {
-          if (candidates[i]) {
+          if (candidates[i + 1]) {
             items.push(
               <text
                 key={`auto-solver-candidate-${y}-${x}-${i}`}
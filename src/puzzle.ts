@@ -1,5 +1,10 @@
 import { allRules } from "./rules/rules";
 
+// The version of the puzzle format the rule data of a `Problem` is in. Must
+// match `CURRENT_VERSION` in solver/src/migrate.rs; older saved documents are
+// upgraded by `loadProblemFromString`.
+export const PUZZLE_VERSION = 2;
+
 export type Problem = {
   size: number;
   enabledRules: string[];
@@ -8,7 +13,9 @@ export type Problem = {
 
 export type Answer = {
   decidedNumbers: (number | null)[][];
-  candidates: boolean[][][];
+  digits: number[];
+  candidates: boolean[][][]; // candidates[y][x][v] for digit v
+  timedOut: boolean;
 } | null;
 
 export const defaultProblem = (size: number, blockWidth: number): Problem => {
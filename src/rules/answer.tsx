@@ -1,26 +1,151 @@
-import { Rule, PRIORITY_ANSWER } from "../rule";
+import {
+  Rule,
+  PRIORITY_ANSWER,
+  PRIORITY_SELECTED_CELL_MARKER,
+} from "../rule";
 import {
   CellNumbersState,
   CellNumbersData,
   cellNumbersRule,
 } from "./cellNumbers";
 
-export const answerRule: Rule<CellNumbersState, CellNumbersData> = {
+type AnswerState = CellNumbersState & {
+  // Whether digit keys toggle pencil marks instead of entering digits.
+  pencilMode: boolean;
+};
+
+type AnswerData = CellNumbersData & {
+  // pencilMarks[y][x] lists the digits the player still considers possible
+  // at (y, x), or is null if the cell has no pencil marks. Missing in
+  // documents saved before pencil marks existed.
+  pencilMarks?: (number[] | null)[][];
+};
+
+const emptyPencilMarks = (size: number) => {
+  const pencilMarks = [];
+  for (let i = 0; i < size; i++) {
+    pencilMarks.push(new Array(size).fill(null));
+  }
+  return pencilMarks;
+};
+
+export const answerRule: Rule<AnswerState, AnswerData> = {
   name: "answer",
-  initialState: cellNumbersRule.initialState,
-  initialData: cellNumbersRule.initialData,
+  initialState: { ...cellNumbersRule.initialState, pencilMode: false },
+  initialData: (size: number) => ({
+    ...cellNumbersRule.initialData(size),
+    pencilMarks: emptyPencilMarks(size),
+  }),
   eventTypes: cellNumbersRule.eventTypes,
-  reducer: cellNumbersRule.reducer,
-  render: (state, data, options) =>
-    cellNumbersRule.render(
-      state,
+  reducer: (state, data, event) => {
+    if (state === undefined) {
+      return {};
+    }
+
+    if (event.type === "keyDown") {
+      const key = event.key;
+      if (key === "p") {
+        return { state: { ...state, pencilMode: !state.pencilMode } };
+      }
+
+      if (state.pencilMode && state.selectedCell !== null) {
+        const { y, x } = state.selectedCell;
+        const size = data.numbers.length;
+        const pencilMarks = (
+          data.pencilMarks ?? emptyPencilMarks(size)
+        ).map((row) => row.slice());
+
+        if (key === "Backspace" || key === "Delete" || key === " ") {
+          pencilMarks[y][x] = null;
+          return { data: { ...data, pencilMarks } };
+        }
+        // Only single-digit marks can be typed.
+        const n = parseInt(key);
+        if (!isNaN(n) && 1 <= n && n <= size) {
+          const marks = pencilMarks[y][x] ?? [];
+          pencilMarks[y][x] = marks.includes(n)
+            ? marks.filter((m) => m !== n)
+            : [...marks, n].sort((a, b) => a - b);
+          return { data: { ...data, pencilMarks } };
+        }
+      }
+    }
+
+    const result = cellNumbersRule.reducer(state, data, event);
+    return {
+      state: result.state && { ...state, ...result.state },
+      data: result.data && { ...data, ...result.data },
+    };
+  },
+  render: (state, data, options) => {
+    const { cellSize, margin } = options;
+
+    // The selected cell is marked in blue instead of red in pencil mode.
+    const pencilMode = state !== null && state.pencilMode;
+    const items = cellNumbersRule.render(
+      pencilMode ? { ...state, selectedCell: null } : state,
       data,
       options,
       "rgb(0, 128, 0)",
       PRIORITY_ANSWER,
-    ),
+    );
+    if (pencilMode && state.selectedCell !== null) {
+      const { y, x } = state.selectedCell;
+      items.push({
+        priority: PRIORITY_SELECTED_CELL_MARKER,
+        item: (
+          <rect
+            x={margin + x * cellSize}
+            y={margin + y * cellSize}
+            width={cellSize}
+            height={cellSize}
+            fill="rgb(216, 216, 255)"
+          />
+        ),
+      });
+    }
+
+    const pencilMarkItems = [];
+    const w = Math.ceil(Math.sqrt(data.numbers.length));
+    const pencilMarks = data.pencilMarks ?? [];
+    for (let y = 0; y < pencilMarks.length; ++y) {
+      for (let x = 0; x < pencilMarks[y].length; ++x) {
+        const marks = pencilMarks[y][x];
+        if (marks === null || data.numbers[y][x] !== null) {
+          continue;
+        }
+        for (const n of marks) {
+          const p = n - 1;
+          pencilMarkItems.push(
+            <text
+              key={`pencil-mark-${y}-${x}-${n}`}
+              x={margin + x * cellSize + (((p % w) + 0.5) / w) * cellSize}
+              y={
+                margin +
+                y * cellSize +
+                ((Math.floor(p / w) + 0.5) / w) * cellSize
+              }
+              fontSize={(cellSize / w) * 0.9}
+              fill="rgb(0, 128, 0)"
+              textAnchor="middle"
+              dominantBaseline="central"
+              style={{ userSelect: "none" }}
+            >
+              {n}
+            </text>,
+          );
+        }
+      }
+    }
+    items.push({
+      priority: PRIORITY_ANSWER,
+      item: <g>{pencilMarkItems}</g>,
+    });
+
+    return items;
+  },
   exportToPenpa: () => {
     return { items: [], margin: 0 };
   },
-  persistentStateKeys: ["selectedCell"],
+  persistentStateKeys: ["selectedCell", "pencilMode"],
 };
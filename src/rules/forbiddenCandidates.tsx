@@ -10,6 +10,8 @@ type ForbiddenCandidatesState = {
 };
 
 type ForbiddenCandidatesData = {
+  // isForbidden[y][x][n] tells whether the digit n is forbidden at (y, x);
+  // index 0 is unused.
   isForbidden: boolean[][][];
 };
 
@@ -24,7 +26,7 @@ export const forbiddenCandidatesRule: Rule<
     for (let i = 0; i < size; ++i) {
       const row = [];
       for (let j = 0; j < size; ++j) {
-        row.push(new Array(size).fill(false));
+        row.push(new Array(size + 1).fill(false));
       }
       isForbidden.push(row);
     }
@@ -88,7 +90,7 @@ export const forbiddenCandidatesRule: Rule<
           const newIsForbidden = data.isForbidden.map((row) =>
             row.map((cell) => cell.slice()),
           );
-          newIsForbidden[y][x][n - 1] = !newIsForbidden[y][x][n - 1];
+          newIsForbidden[y][x][n] = !newIsForbidden[y][x][n];
           const newData = { isForbidden: newIsForbidden };
           return { data: newData };
         }
@@ -125,14 +127,15 @@ export const forbiddenCandidatesRule: Rule<
 
     for (let y = 0; y < data.isForbidden.length; ++y) {
       for (let x = 0; x < data.isForbidden[y].length; ++x) {
-        for (let n = 0; n < data.isForbidden[y][x].length; ++n) {
+        for (let n = 1; n < data.isForbidden[y][x].length; ++n) {
           if (data.isForbidden[y][x][n]) {
+            const p = n - 1;
             const textY =
               margin +
               y * cellSize +
-              ((Math.floor(n / w) + 0.5) / w) * cellSize;
+              ((Math.floor(p / w) + 0.5) / w) * cellSize;
             const textX =
-              margin + x * cellSize + (((n % w) + 0.5) / w) * cellSize;
+              margin + x * cellSize + (((p % w) + 0.5) / w) * cellSize;
             forbiddenMarkers.push(
               <text
                 key={`forbidden-${y}-${x}-${n}`}
@@ -144,7 +147,7 @@ export const forbiddenCandidatesRule: Rule<
                 dominantBaseline="central"
                 style={{ userSelect: "none" }}
               >
-                {n + 1}
+                {n}
               </text>,
             );
 
@@ -191,7 +194,7 @@ export const forbiddenCandidatesRule: Rule<
         const forbiddens = data.isForbidden[y][x];
         if (forbiddens.some((v) => v)) {
           const value = forbiddens
-            .map((v, i) => (v ? (i + 1).toString() : null))
+            .map((v, i) => (v ? i.toString() : null))
             .filter((v) => v !== null)
             .join("");
           items.push({
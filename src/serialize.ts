@@ -1,6 +1,6 @@
 import lzstring from "lz-string";
 import { allRules } from "./rules/rules";
-import { Problem } from "./puzzle";
+import { Problem, PUZZLE_VERSION } from "./puzzle";
 
 export const saveProblemAsString = (problem: Problem): string => {
   const ruleData: any = {}; // eslint-disable-line @typescript-eslint/no-explicit-any
@@ -10,6 +10,7 @@ export const saveProblemAsString = (problem: Problem): string => {
     }
   }
   const json = JSON.stringify({
+    version: PUZZLE_VERSION,
     size: problem.size,
     enabledRules: problem.enabledRules,
     ruleData,
@@ -17,13 +18,36 @@ export const saveProblemAsString = (problem: Problem): string => {
   return lzstring.compressToEncodedURIComponent(json);
 };
 
+// Upgrades rule data saved in an older format version in place, like the
+// migrations of solver/src/migrate.rs.
+const migrateRuleData = (
+  version: number,
+  ruleData: Map<string, any>, // eslint-disable-line @typescript-eslint/no-explicit-any
+) => {
+  if (version < 2 && ruleData.has("forbiddenCandidates")) {
+    // isForbidden[y][x] used to be indexed by the position of the digit in
+    // 1..size, which is the digit minus 1.
+    const { isForbidden } = ruleData.get("forbiddenCandidates");
+    ruleData.set("forbiddenCandidates", {
+      isForbidden: isForbidden.map((row: boolean[][]) =>
+        row.map((cell) => [false, ...cell]),
+      ),
+    });
+  }
+};
+
 export const loadProblemFromString = (str: string): Problem => {
   const json = lzstring.decompressFromEncodedURIComponent(str);
   if (json === null) {
     throw new Error("Invalid problem string");
   }
-  const { size, enabledRules, ruleData } = JSON.parse(json);
+  // Documents saved before the format was versioned have no `version`.
+  const { version = 0, size, enabledRules, ruleData } = JSON.parse(json);
+  if (version > PUZZLE_VERSION) {
+    throw new Error(`Unsupported problem version ${version}`);
+  }
   const ruleDataMap = new Map(Object.entries(ruleData));
+  migrateRuleData(version, ruleDataMap);
 
   for (const rule of allRules) {
     if (!enabledRules.includes(rule.name)) {
@@ -1,3 +1,135 @@
 import { Problem, Answer } from "./puzzle";
 
-export declare function solve(problem: Problem): Answer;
+export type Diagnostic = {
+  severity: "error" | "warning";
+  code: "parseError" | "shapeMismatch" | "outOfRange" | "unsupportedValue";
+  rule: string;
+  index: number | null;
+  cells: { x: number; y: number }[];
+  message: string;
+};
+
+export type RuleInstance = {
+  rule: string;
+  index: number;
+  cells: { x: number; y: number }[];
+};
+
+export type SolutionCount = {
+  count: number;
+  limitReached: boolean;
+};
+
+export type Ambiguity = {
+  solutions: [number[][], number[][]];
+  differingCells: { x: number; y: number }[];
+};
+
+export type Candidate = {
+  cell: { x: number; y: number };
+  digit: number;
+};
+
+export type ProgressReport = {
+  wrongEntries: Candidate[];
+  // Checked against `answer.pencilMarks`, entered in the pencil mode of the
+  // answer rule.
+  wrongEliminations: Candidate[];
+  solvable: boolean;
+  timedOut: boolean;
+};
+
+export type Hint = {
+  kind: "placement" | "elimination";
+  cell: { x: number; y: number };
+  digit: number;
+  reason: RuleInstance[];
+};
+
+export type BreakIns = {
+  // Ranked by the size of `reason`, which no instance can be dropped from but
+  // which is not necessarily the smallest justification.
+  deductions: Hint[];
+  timedOut: boolean;
+};
+
+export type Technique =
+  | "hiddenSingle"
+  | "nakedSingle"
+  | "pairRestriction"
+  | "palindromeEquality"
+  | "thermoRange"
+  | "cageCombination"
+  | "lockedCandidates"
+  | "arrowSum"
+  | "inniesOuties"
+  | "nakedPair"
+  | "hiddenPair"
+  | "nakedTriple"
+  | "hiddenTriple"
+  | "xWing"
+  | "nakedQuad"
+  | "hiddenQuad"
+  | "swordfish"
+  | "jellyfish"
+  | "xyChain"
+  | "alternatingInferenceChain";
+
+export type Link = {
+  from: Candidate;
+  to: Candidate;
+  strong: boolean;
+};
+
+export type TraceStep = {
+  technique: Technique;
+  instances: number[];
+  cells: { x: number; y: number }[];
+  candidates: Candidate[];
+  links: Link[];
+  placements: Candidate[];
+  eliminations: Candidate[];
+};
+
+export type Trace = {
+  instances: RuleInstance[];
+  steps: TraceStep[];
+  decidedNumbers: (number | null)[][];
+  solved: boolean;
+};
+
+// With `timeLimitMs` (0 for no limit), returns the facts known so far once the
+// time is up (`timedOut: true`). The solver runs on the calling thread, so this
+// is the only way to bound a call; it cannot be cancelled from JS while it runs.
+export declare function solve(
+  problem: Problem,
+  timeLimitMs?: number,
+): Answer;
+// `limit` must be at least 1.
+export declare function countSolutions(
+  problem: Problem,
+  limit: number,
+): SolutionCount | null;
+export declare function findAmbiguity(problem: Problem): Ambiguity | null;
+export declare function explainElimination(
+  problem: Problem,
+  y: number,
+  x: number,
+  digit: number,
+): RuleInstance[] | null;
+export declare function checkProgress(
+  problem: Problem,
+): ProgressReport | null;
+// Returns the deduction with the fewest rule instances in its `reason`. Reasons
+// are only minimal in that no instance can be dropped from them, so this is a
+// heuristic for the easiest deduction rather than a guarantee.
+export declare function nextHint(
+  problem: Problem,
+  timeLimitMs?: number,
+): Hint | null;
+export declare function findBreakIns(
+  problem: Problem,
+  timeLimitMs?: number,
+): BreakIns | null;
+export declare function traceLogicalSolve(problem: Problem): Trace | null;
+export declare function validate(problem: Problem): Diagnostic[];
@@ -1,9 +1,19 @@
 import Module from "./solver/sudoku_variants_solver.js";
+import { PUZZLE_VERSION } from "./puzzle";
 
 let Solver = await Module();
 
-export function solve(problem) {
-  const puzzle = { size: problem.size };
+// Problems hold rule data in the editor's format version, which is sent with
+// every call so that the solver can migrate it (see solver/src/migrate.rs).
+// A solver older than the editor cannot read it.
+if (Solver._puzzle_version() < PUZZLE_VERSION) {
+  console.error(
+    `solver supports puzzle version ${Solver._puzzle_version()}, but the editor uses ${PUZZLE_VERSION}`,
+  );
+}
+
+function callSolver(func, problem, ...args) {
+  const puzzle = { version: PUZZLE_VERSION, size: problem.size };
   for (const rule of problem.enabledRules) {
     puzzle[rule] = problem.ruleData.get(rule);
   }
@@ -15,7 +25,7 @@ export function solve(problem) {
   const buf = Solver._malloc(puzzleJsonEncoded.length);
   Solver.HEAPU8.set(puzzleJsonEncoded, buf);
 
-  let res = Solver._solve_problem(buf, puzzleJsonEncoded.length);
+  let res = func(buf, puzzleJsonEncoded.length, ...args);
   const length =
     Solver.HEAPU8[res] |
     (Solver.HEAPU8[res + 1] << 8) |
@@ -27,3 +37,108 @@ export function solve(problem) {
   );
   return JSON.parse(resStr);
 }
+
+export function solve(problem, timeLimitMs) {
+  const response =
+    timeLimitMs === undefined
+      ? callSolver(Solver._solve_problem, problem)
+      : callSolver(
+          Solver._solve_problem_with_time_limit,
+          problem,
+          timeLimitMs,
+        );
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  if (response.result.kind === "unsatisfiable") {
+    return null;
+  }
+  return response.result;
+}
+
+export function countSolutions(problem, limit) {
+  const response = callSolver(Solver._count_solutions, problem, limit);
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  return response.result;
+}
+
+export function findAmbiguity(problem) {
+  const response = callSolver(Solver._find_ambiguity, problem);
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  return response.result;
+}
+
+export function explainElimination(problem, y, x, digit) {
+  const response = callSolver(
+    Solver._explain_elimination,
+    problem,
+    y,
+    x,
+    digit,
+  );
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  return response.result;
+}
+
+export function checkProgress(problem) {
+  const response = callSolver(Solver._check_progress, problem);
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  return response.result;
+}
+
+export function nextHint(problem, timeLimitMs) {
+  const response = callSolver(
+    Solver._next_hint,
+    problem,
+    timeLimitMs === undefined ? 0 : timeLimitMs,
+  );
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  return response.result;
+}
+
+export function findBreakIns(problem, timeLimitMs) {
+  const response = callSolver(
+    Solver._find_break_ins,
+    problem,
+    timeLimitMs === undefined ? 0 : timeLimitMs,
+  );
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  return response.result;
+}
+
+export function traceLogicalSolve(problem) {
+  const response = callSolver(Solver._trace_logical_solve, problem);
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return null;
+  }
+  return response.result;
+}
+
+export function validate(problem) {
+  const response = callSolver(Solver._validate_problem, problem);
+  if (response.status === "error") {
+    console.error(`solver error (${response.code}): ${response.message}`);
+    return [];
+  }
+  return response.result;
+}
@@ -0,0 +1,57 @@
+import { describe, it, expect } from "vitest";
+import lzstring from "lz-string";
+
+import { loadProblemFromString, saveProblemAsString } from "../serialize";
+import { PUZZLE_VERSION } from "../puzzle";
+
+const emptyIsForbidden = (size: number, length: number) =>
+  Array(size)
+    .fill(null)
+    .map(() =>
+      Array(size)
+        .fill(null)
+        .map(() => Array(length).fill(false)),
+    );
+
+describe("problem serialization", () => {
+  it("should index forbidden candidates of unversioned problems by value", () => {
+    const isForbidden = emptyIsForbidden(4, 4);
+    isForbidden[0][1][0] = true;
+    isForbidden[2][3][3] = true;
+    const saved = lzstring.compressToEncodedURIComponent(
+      JSON.stringify({
+        size: 4,
+        enabledRules: ["forbiddenCandidates"],
+        ruleData: { forbiddenCandidates: { isForbidden } },
+      }),
+    );
+
+    const problem = loadProblemFromString(saved);
+
+    const expected = emptyIsForbidden(4, 5);
+    expected[0][1][1] = true;
+    expected[2][3][4] = true;
+    expect(problem.ruleData.get("forbiddenCandidates")).toStrictEqual({
+      isForbidden: expected,
+    });
+  });
+
+  it("should keep forbidden candidates of current problems", () => {
+    const isForbidden = emptyIsForbidden(4, 5);
+    isForbidden[1][1][2] = true;
+    const problem = {
+      size: 4,
+      enabledRules: ["forbiddenCandidates"],
+      ruleData: new Map([["forbiddenCandidates", { isForbidden }]]),
+    };
+
+    const saved = saveProblemAsString(problem);
+    const json = JSON.parse(
+      lzstring.decompressFromEncodedURIComponent(saved) as string,
+    );
+    expect(json.version).toBe(PUZZLE_VERSION);
+    expect(
+      loadProblemFromString(saved).ruleData.get("forbiddenCandidates"),
+    ).toStrictEqual({ isForbidden });
+  });
+});